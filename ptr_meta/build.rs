@@ -0,0 +1,27 @@
+use std::{env, process::Command};
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(ptr_meta_strict_provenance)");
+    println!("cargo:rustc-check-cfg=cfg(ptr_meta_trait_upcasting)");
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let minor = rustc_minor_version();
+
+    // The strict provenance APIs were stabilized in Rust 1.84.
+    if minor.is_some_and(|minor| minor >= 84) {
+        println!("cargo:rustc-cfg=ptr_meta_strict_provenance");
+    }
+
+    // Trait upcasting coercions were stabilized in Rust 1.86.
+    if minor.is_some_and(|minor| minor >= 86) {
+        println!("cargo:rustc-cfg=ptr_meta_trait_upcasting");
+    }
+}
+
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    let mut parts = version.strip_prefix("rustc 1.")?.split('.');
+    parts.next()?.parse().ok()
+}
@@ -0,0 +1,200 @@
+use core::{
+    any::TypeId,
+    fmt,
+    hash::{Hash, Hasher},
+    mem::{size_of, MaybeUninit},
+    ptr,
+};
+
+use crate::{from_raw_parts, to_raw_parts, Pointee};
+
+/// Erases the type of pointer metadata by storing it in a pointer-sized word.
+///
+/// Every `Pointee::Metadata` provided by this crate (`()`, `usize`, and
+/// `DynMetadata`) fits in a word. Storing the word as a pointer preserves the
+/// provenance of `DynMetadata`'s vtable pointer.
+#[inline]
+pub(crate) fn erase_metadata<T: Pointee + ?Sized>(
+    metadata: T::Metadata,
+) -> *mut () {
+    const {
+        assert!(
+            size_of::<T::Metadata>() <= size_of::<*mut ()>(),
+            "the metadata must not be larger than a pointer",
+        );
+    }
+
+    let mut word = MaybeUninit::new(ptr::null_mut::<()>());
+    // SAFETY: The metadata is no larger than `word`, and it is written
+    // unaligned. `word` remains fully initialized after the write.
+    unsafe {
+        word.as_mut_ptr()
+            .cast::<T::Metadata>()
+            .write_unaligned(metadata);
+        word.assume_init()
+    }
+}
+
+/// Restores metadata erased by [`erase_metadata`].
+///
+/// # Safety
+///
+/// `word` must have been returned by `erase_metadata::<T>`.
+#[inline]
+pub(crate) unsafe fn restore_metadata<T: Pointee + ?Sized>(
+    word: *mut (),
+) -> T::Metadata {
+    // SAFETY: The caller has guaranteed that `word` begins with a valid
+    // `T::Metadata`.
+    unsafe { ptr::addr_of!(word).cast::<T::Metadata>().read_unaligned() }
+}
+
+/// A pointer to a value of any `'static` type, including unsized types.
+///
+/// An `AnyPtr` stores the data address of a pointer, a type-erased copy of its
+/// metadata, and the [`TypeId`] of its pointee. It can be downcast back to a
+/// pointer of the original type, which makes it possible to store thin,
+/// slice, and trait object pointers side-by-side.
+///
+/// # Example
+///
+/// ```
+/// use core::any::Any;
+///
+/// use ptr_meta::AnyPtr;
+///
+/// let text = "hello";
+/// let bytes = [1u8, 2, 3];
+/// let value: &dyn Any = &42u32;
+///
+/// let pointers = [
+///     AnyPtr::new(text),
+///     AnyPtr::new(&bytes[..]),
+///     AnyPtr::new(value),
+/// ];
+///
+/// let text = pointers[0].downcast::<str>().unwrap();
+/// assert_eq!(unsafe { &*text }, "hello");
+/// assert!(pointers[1].downcast::<str>().is_none());
+/// assert_eq!(pointers[1].downcast::<[u8]>().unwrap().len(), 3);
+///
+/// let value = pointers[2].downcast::<dyn Any>().unwrap();
+/// assert_eq!(unsafe { &*value }.downcast_ref::<u32>(), Some(&42));
+/// ```
+#[derive(Clone, Copy)]
+pub struct AnyPtr {
+    data_address: *const (),
+    metadata: *mut (),
+    type_id: TypeId,
+}
+
+impl AnyPtr {
+    /// Returns a new `AnyPtr` from a pointer.
+    #[inline]
+    pub fn new<T: Pointee + ?Sized + 'static>(ptr: *const T) -> Self {
+        let (data_address, metadata) = to_raw_parts(ptr);
+        Self {
+            data_address,
+            metadata: erase_metadata::<T>(metadata),
+            type_id: TypeId::of::<T>(),
+        }
+    }
+
+    /// Returns the data address of the pointer.
+    #[inline]
+    pub fn data_address(&self) -> *const () {
+        self.data_address
+    }
+
+    /// Returns the `TypeId` of the pointee type.
+    #[inline]
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Returns whether the pointee type is `T`.
+    #[inline]
+    pub fn is<T: ?Sized + 'static>(&self) -> bool {
+        self.type_id == TypeId::of::<T>()
+    }
+
+    /// Returns the pointer as a `*const T`, or `None` if the pointee type is
+    /// not `T`.
+    #[inline]
+    pub fn downcast<T: Pointee + ?Sized + 'static>(&self) -> Option<*const T> {
+        if !self.is::<T>() {
+            return None;
+        }
+
+        // SAFETY: The pointee type is `T`, so the metadata was erased from a
+        // `T::Metadata`.
+        let metadata = unsafe { restore_metadata::<T>(self.metadata) };
+        Some(from_raw_parts(self.data_address, metadata))
+    }
+}
+
+impl PartialEq for AnyPtr {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.data_address == other.data_address
+            && self.metadata == other.metadata
+            && self.type_id == other.type_id
+    }
+}
+
+impl Eq for AnyPtr {}
+
+impl Hash for AnyPtr {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.data_address.hash(state);
+        self.metadata.hash(state);
+        self.type_id.hash(state);
+    }
+}
+
+impl fmt::Debug for AnyPtr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnyPtr")
+            .field("data_address", &self.data_address)
+            .field("type_id", &self.type_id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{any::Any, ffi::CStr, ptr};
+
+    use super::AnyPtr;
+    use crate::DynMetadata;
+
+    #[test]
+    fn round_trips() {
+        let number = 7u64;
+        let thin = AnyPtr::new(&number);
+        assert!(thin.is::<u64>());
+        assert!(ptr::eq(thin.downcast::<u64>().unwrap(), &number));
+        assert!(thin.downcast::<u32>().is_none());
+
+        let c: &CStr = c"hi";
+        let c_ptr = AnyPtr::new(c);
+        // SAFETY: `c_ptr` points to `c`.
+        assert_eq!(unsafe { &*c_ptr.downcast::<CStr>().unwrap() }, c);
+        assert!(c_ptr.downcast::<[u8]>().is_none());
+
+        let any: &(dyn Any + Send) = &'x';
+        let dyn_ptr = AnyPtr::new(any as *const (dyn Any + Send));
+        assert!(dyn_ptr.downcast::<dyn Any>().is_none());
+        let restored = dyn_ptr.downcast::<dyn Any + Send>().unwrap();
+        assert_eq!(
+            crate::metadata(restored),
+            crate::metadata(any) as DynMetadata<dyn Any + Send>,
+        );
+        // SAFETY: `restored` points to `'x'`.
+        assert_eq!(unsafe { &*restored }.downcast_ref::<char>(), Some(&'x'));
+
+        assert_eq!(c_ptr, AnyPtr::new(c));
+        assert_ne!(AnyPtr::new(&[1u8, 2][..1]), AnyPtr::new(&[1u8, 2][..]));
+    }
+}
@@ -0,0 +1,450 @@
+use core::{
+    fmt,
+    hint::spin_loop,
+    marker::PhantomData,
+    sync::atomic::{fence, AtomicPtr, AtomicUsize, Ordering},
+};
+
+use crate::{
+    any_ptr::{erase_metadata, restore_metadata},
+    from_raw_parts_mut, to_raw_parts_mut, Pointee,
+};
+
+/// A pair of words which can be compared and exchanged together on targets
+/// with a double-width compare-exchange.
+#[cfg_attr(target_arch = "x86_64", repr(C, align(16)))]
+#[cfg_attr(not(target_arch = "x86_64"), repr(C))]
+struct Words([AtomicPtr<()>; 2]);
+
+/// A possibly-wide pointer which can be safely shared between threads.
+///
+/// This is the counterpart to [`AtomicPtr`] for pointers to unsized types
+/// such as `*mut [u8]` and `*mut dyn Trait`. The pointer is split into its data
+/// address and metadata with [`to_raw_parts_mut`] when stored and rebuilt
+/// with [`from_raw_parts_mut`] when loaded.
+///
+/// On x86_64 targets which support `cmpxchg16b`, every operation is
+/// lock-free. Otherwise, operations are synchronized with a sequence lock:
+/// loads never block writers, and writers spin while another write is in
+/// progress. [`is_lock_free`](AtomicWidePtr::is_lock_free) reports which
+/// implementation is used.
+///
+/// Every operation acts on the whole pointer at once, so a load never observes
+/// the data address of one pointer combined with the metadata of another.
+/// Stores, swaps, and successful compare-exchanges have release semantics;
+/// loads, swaps, and compare-exchanges have acquire semantics.
+///
+/// # Example
+///
+/// ```
+/// use ptr_meta::AtomicWidePtr;
+///
+/// let mut short = [1u8, 2];
+/// let mut long = [3u8, 4, 5, 6];
+///
+/// let atomic = AtomicWidePtr::new(&mut short[..] as *mut [u8]);
+/// let previous = atomic.swap(&mut long[..]);
+/// assert_eq!(previous.len(), 2);
+/// assert_eq!(atomic.load().len(), 4);
+///
+/// let current = atomic.load();
+/// assert!(atomic.compare_exchange(current, previous).is_ok());
+/// assert_eq!(unsafe { &*atomic.load() }, &[1, 2]);
+/// ```
+pub struct AtomicWidePtr<T: Pointee + ?Sized> {
+    words: Words,
+    sequence: AtomicUsize,
+    _phantom: PhantomData<*mut T>,
+}
+
+// SAFETY: Like `AtomicPtr`, `AtomicWidePtr` only stores a pointer and all
+// accesses to it are synchronized.
+unsafe impl<T: Pointee + ?Sized> Send for AtomicWidePtr<T> {}
+
+// SAFETY: Like `AtomicPtr`, `AtomicWidePtr` only stores a pointer and all
+// accesses to it are synchronized.
+unsafe impl<T: Pointee + ?Sized> Sync for AtomicWidePtr<T> {}
+
+impl<T: Pointee + ?Sized> AtomicWidePtr<T> {
+    /// Returns a new atomic pointer.
+    #[inline]
+    pub fn new(ptr: *mut T) -> Self {
+        let [data_address, metadata] = Self::split(ptr);
+        Self {
+            words: Words([
+                AtomicPtr::new(data_address),
+                AtomicPtr::new(metadata),
+            ]),
+            sequence: AtomicUsize::new(0),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the contained pointer.
+    #[inline]
+    pub fn into_inner(self) -> *mut T {
+        let [data_address, metadata] = self.words.0;
+        Self::join([data_address.into_inner(), metadata.into_inner()])
+    }
+
+    /// Returns whether operations on `AtomicWidePtr` are lock-free on the
+    /// current target.
+    #[inline]
+    pub fn is_lock_free() -> bool {
+        #[cfg(all(target_arch = "x86_64", target_feature = "cmpxchg16b"))]
+        {
+            true
+        }
+        #[cfg(all(
+            target_arch = "x86_64",
+            not(target_feature = "cmpxchg16b"),
+            feature = "std",
+        ))]
+        {
+            std::is_x86_feature_detected!("cmpxchg16b")
+        }
+        #[cfg(not(all(
+            target_arch = "x86_64",
+            any(target_feature = "cmpxchg16b", feature = "std"),
+        )))]
+        {
+            false
+        }
+    }
+
+    /// Loads the pointer.
+    #[inline]
+    pub fn load(&self) -> *mut T {
+        #[cfg(target_arch = "x86_64")]
+        if Self::is_lock_free() {
+            return Self::join(self.cas_words(Self::NULL, Self::NULL).0);
+        }
+
+        Self::join(self.seq_load())
+    }
+
+    /// Stores a pointer.
+    #[inline]
+    pub fn store(&self, ptr: *mut T) {
+        self.swap(ptr);
+    }
+
+    /// Stores a pointer and returns the previous pointer.
+    #[inline]
+    pub fn swap(&self, ptr: *mut T) -> *mut T {
+        let new = Self::split(ptr);
+
+        #[cfg(target_arch = "x86_64")]
+        if Self::is_lock_free() {
+            let mut current = self.cas_words(Self::NULL, Self::NULL).0;
+            loop {
+                match self.cas_words(current, new) {
+                    (previous, true) => return Self::join(previous),
+                    (previous, false) => current = previous,
+                }
+            }
+        }
+
+        Self::join(self.seq_write(|_| Some(new)))
+    }
+
+    /// Stores `new` if the current pointer is equal to `current`.
+    ///
+    /// Pointers are equal if both their data addresses and their metadata are
+    /// equal. On success, returns the previous pointer in `Ok`. On failure,
+    /// returns the current pointer in `Err`.
+    #[inline]
+    pub fn compare_exchange(
+        &self,
+        current: *mut T,
+        new: *mut T,
+    ) -> Result<*mut T, *mut T> {
+        let current = Self::split(current);
+        let new = Self::split(new);
+
+        #[cfg(target_arch = "x86_64")]
+        if Self::is_lock_free() {
+            return match self.cas_words(current, new) {
+                (previous, true) => Ok(Self::join(previous)),
+                (previous, false) => Err(Self::join(previous)),
+            };
+        }
+
+        let previous = self.seq_write(|previous| {
+            Self::words_eq(previous, current).then_some(new)
+        });
+        if Self::words_eq(previous, current) {
+            Ok(Self::join(previous))
+        } else {
+            Err(Self::join(previous))
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    const NULL: [*mut (); 2] = [core::ptr::null_mut(); 2];
+
+    #[inline]
+    fn split(ptr: *mut T) -> [*mut (); 2] {
+        let (data_address, metadata) = to_raw_parts_mut(ptr);
+        [data_address, erase_metadata::<T>(metadata)]
+    }
+
+    #[inline]
+    fn join([data_address, metadata]: [*mut (); 2]) -> *mut T {
+        // SAFETY: Every metadata word was produced by `split`, which erased a
+        // `T::Metadata`.
+        let metadata = unsafe { restore_metadata::<T>(metadata) };
+        from_raw_parts_mut(data_address, metadata)
+    }
+
+    #[inline]
+    fn words_eq(a: [*mut (); 2], b: [*mut (); 2]) -> bool {
+        a[0] == b[0] && a[1] == b[1]
+    }
+
+    /// Compares the stored words with `current` and replaces them with `new`
+    /// if they are equal. Returns the previous words and whether they were
+    /// replaced.
+    #[cfg(target_arch = "x86_64")]
+    #[inline]
+    fn cas_words(
+        &self,
+        current: [*mut (); 2],
+        new: [*mut (); 2],
+    ) -> ([*mut (); 2], bool) {
+        let dst = self.words.0.as_ptr();
+        let (previous_lo, previous_hi, success): (*mut (), *mut (), u8);
+        // SAFETY: This is only called when `cmpxchg16b` is available. `dst`
+        // is valid for reads and writes and aligned to 16 bytes. `rbx` is
+        // reserved by LLVM, so it is saved and restored around the
+        // instruction.
+        unsafe {
+            core::arch::asm!(
+                "xchg {rbx_tmp}, rbx",
+                "lock cmpxchg16b xmmword ptr [{dst}]",
+                "mov rbx, {rbx_tmp}",
+                "sete {success}",
+                dst = in(reg) dst,
+                rbx_tmp = inout(reg) new[0] => _,
+                success = out(reg_byte) success,
+                in("rcx") new[1],
+                inout("rax") current[0] => previous_lo,
+                inout("rdx") current[1] => previous_hi,
+                options(nostack),
+            );
+        }
+        ([previous_lo, previous_hi], success != 0)
+    }
+
+    /// Loads the words under the sequence lock.
+    #[inline]
+    fn seq_load(&self) -> [*mut (); 2] {
+        loop {
+            let before = self.sequence.load(Ordering::Acquire);
+            if before & 1 == 0 {
+                let words = [
+                    self.words.0[0].load(Ordering::Relaxed),
+                    self.words.0[1].load(Ordering::Relaxed),
+                ];
+                fence(Ordering::Acquire);
+                if self.sequence.load(Ordering::Relaxed) == before {
+                    return words;
+                }
+            }
+            spin_loop();
+        }
+    }
+
+    /// Locks the sequence lock, passes the current words to `update`, and
+    /// stores the words it returns, if any. Returns the previous words.
+    #[inline]
+    fn seq_write(
+        &self,
+        update: impl FnOnce([*mut (); 2]) -> Option<[*mut (); 2]>,
+    ) -> [*mut (); 2] {
+        let mut sequence = self.sequence.load(Ordering::Relaxed);
+        loop {
+            if sequence & 1 == 0 {
+                match self.sequence.compare_exchange_weak(
+                    sequence,
+                    sequence.wrapping_add(1),
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => break,
+                    Err(current) => sequence = current,
+                }
+            } else {
+                spin_loop();
+                sequence = self.sequence.load(Ordering::Relaxed);
+            }
+        }
+        fence(Ordering::Release);
+
+        let previous = [
+            self.words.0[0].load(Ordering::Relaxed),
+            self.words.0[1].load(Ordering::Relaxed),
+        ];
+        if let Some(new) = update(previous) {
+            self.words.0[0].store(new[0], Ordering::Relaxed);
+            self.words.0[1].store(new[1], Ordering::Relaxed);
+        }
+
+        self.sequence
+            .store(sequence.wrapping_add(2), Ordering::Release);
+        previous
+    }
+}
+
+impl<T: Pointee + ?Sized> fmt::Debug for AtomicWidePtr<T>
+where
+    T::Metadata: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (data_address, metadata) = to_raw_parts_mut(self.load());
+        f.debug_struct("AtomicWidePtr")
+            .field("data_address", &data_address)
+            .field("metadata", &metadata)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{any::Any, ptr};
+
+    use super::AtomicWidePtr;
+
+    #[test]
+    fn operations() {
+        let mut a = [1u32, 2, 3];
+        let mut b = [4u32; 7];
+        let a = &mut a[..] as *mut [u32];
+        let b = &mut b[..] as *mut [u32];
+
+        let atomic = AtomicWidePtr::new(a);
+        assert!(ptr::eq(atomic.load(), a));
+        assert!(ptr::eq(atomic.swap(b), a));
+        assert_eq!(atomic.load().len(), 7);
+
+        assert_eq!(atomic.compare_exchange(a, a), Err(b));
+        assert_eq!(atomic.compare_exchange(b, a), Ok(b));
+        // Same data address, different metadata.
+        let short = ptr::slice_from_raw_parts_mut(a.cast::<u32>(), 1);
+        assert!(atomic.compare_exchange(short, b).is_err());
+
+        atomic.store(short);
+        assert_eq!(atomic.into_inner(), short);
+    }
+
+    #[test]
+    fn sequence_lock() {
+        let mut a = 1u8;
+        let mut b = 2u16;
+        let a = &mut a as &mut dyn Any as *mut dyn Any;
+        let b = &mut b as &mut dyn Any as *mut dyn Any;
+
+        let atomic = AtomicWidePtr::new(a);
+        let split_b = AtomicWidePtr::<dyn Any>::split(b);
+        let previous = atomic.seq_write(|_| Some(split_b));
+        assert!(ptr::eq(AtomicWidePtr::<dyn Any>::join(previous), a));
+        let loaded = AtomicWidePtr::<dyn Any>::join(atomic.seq_load());
+        // SAFETY: `loaded` points to `b`.
+        assert_eq!(unsafe { &*loaded }.downcast_ref::<u16>(), Some(&2));
+        assert!(ptr::eq(atomic.into_inner(), b));
+    }
+
+    #[cfg(all(feature = "std", target_os = "linux"))]
+    mod stress {
+        use std::{
+            sync::{
+                atomic::{AtomicBool, Ordering},
+                Arc,
+            },
+            thread,
+        };
+
+        use super::AtomicWidePtr;
+
+        static SHORT: [u8; 3] = [1; 3];
+        static LONG: [u8; 11] = [2; 11];
+
+        fn short() -> *mut [u8] {
+            &SHORT[..] as *const [u8] as *mut [u8]
+        }
+
+        fn long() -> *mut [u8] {
+            &LONG[..] as *const [u8] as *mut [u8]
+        }
+
+        fn is_whole(ptr: *mut [u8]) -> bool {
+            (ptr.cast::<u8>() == SHORT.as_ptr().cast_mut() && ptr.len() == 3)
+                || (ptr.cast::<u8>() == LONG.as_ptr().cast_mut()
+                    && ptr.len() == 11)
+        }
+
+        fn run(
+            write: fn(&AtomicWidePtr<[u8]>, *mut [u8]),
+            read: fn(&AtomicWidePtr<[u8]>) -> *mut [u8],
+        ) {
+            let atomic = Arc::new(AtomicWidePtr::new(short()));
+            let done = Arc::new(AtomicBool::new(false));
+
+            let readers = (0..3)
+                .map(|_| {
+                    let atomic = atomic.clone();
+                    let done = done.clone();
+                    thread::spawn(move || {
+                        while !done.load(Ordering::Relaxed) {
+                            assert!(is_whole(read(&atomic)), "torn read");
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            let writers = (0..2)
+                .map(|_| {
+                    let atomic = atomic.clone();
+                    thread::spawn(move || {
+                        for i in 0..20_000 {
+                            write(
+                                &atomic,
+                                if i % 2 == 0 { long() } else { short() },
+                            );
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for writer in writers {
+                writer.join().unwrap();
+            }
+            done.store(true, Ordering::Relaxed);
+            for reader in readers {
+                reader.join().unwrap();
+            }
+        }
+
+        #[test]
+        fn no_torn_reads() {
+            run(
+                |atomic, ptr| {
+                    let current = atomic.load();
+                    let _ = atomic.compare_exchange(current, ptr);
+                    atomic.store(ptr);
+                },
+                AtomicWidePtr::load,
+            );
+        }
+
+        #[test]
+        fn no_torn_reads_with_sequence_lock() {
+            run(
+                |atomic, ptr| {
+                    let new = AtomicWidePtr::<[u8]>::split(ptr);
+                    atomic.seq_write(|_| Some(new));
+                },
+                |atomic| AtomicWidePtr::<[u8]>::join(atomic.seq_load()),
+            );
+        }
+    }
+}
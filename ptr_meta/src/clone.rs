@@ -0,0 +1,250 @@
+#[cfg(target_has_atomic = "ptr")]
+use alloc::sync::Arc;
+use alloc::{
+    alloc::{alloc, dealloc, handle_alloc_error},
+    boxed::Box,
+};
+use core::{alloc::Layout, ffi::CStr, ptr};
+
+use crate::{from_raw_parts_mut, metadata, Pointee};
+
+/// A type which can be cloned into new allocations even if it is unsized.
+///
+/// This is the unsized counterpart to [`Clone`]. It is implemented for slices
+/// of `Clone` elements, `str`, `CStr`, and `OsStr`.
+///
+/// `#[derive(Pointee)]` implements `CloneUnsized` and [`ToOwned`] for structs
+/// with the `#[ptr_meta(clone_unsized)]` attribute when every field except the
+/// last is `Clone` and the last field is `CloneUnsized`. This makes
+/// `Cow<'_, T>` usable with those structs.
+///
+/// [`ToOwned`]: alloc::borrow::ToOwned
+///
+/// # Example
+///
+/// ```
+/// use std::borrow::Cow;
+///
+/// use ptr_meta::{CloneUnsized, Pointee};
+///
+/// #[derive(Pointee)]
+/// #[ptr_meta(clone_unsized)]
+/// #[repr(C)]
+/// struct Block<H, T> {
+///     header: H,
+///     elements: [T],
+/// }
+///
+/// let original: Box<Block<String, u32>> =
+///     ptr_meta::DstBuilder::new("abc".into()).build_from_slice(&[1, 2, 3]);
+/// let cloned = original.clone_to_box();
+/// assert_eq!(cloned.header, "abc");
+/// assert_eq!(cloned.elements, [1, 2, 3]);
+///
+/// let cow: Cow<'_, Block<String, u32>> = Cow::Borrowed(&original);
+/// let owned: Box<Block<String, u32>> = cow.into_owned();
+/// assert_eq!(owned.elements, [1, 2, 3]);
+/// ```
+pub trait CloneUnsized: Pointee {
+    /// Clones `self` into a new `Box`.
+    fn clone_to_box(&self) -> Box<Self>;
+
+    /// Clones `self` into a new `Arc`.
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    fn clone_to_arc(&self) -> Arc<Self> {
+        Arc::from(self.clone_to_box())
+    }
+}
+
+impl<T: Clone> CloneUnsized for [T] {
+    #[inline]
+    fn clone_to_box(&self) -> Box<Self> {
+        Box::from(self)
+    }
+
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    fn clone_to_arc(&self) -> Arc<Self> {
+        Arc::from(self)
+    }
+}
+
+impl CloneUnsized for str {
+    #[inline]
+    fn clone_to_box(&self) -> Box<Self> {
+        Box::from(self)
+    }
+
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    fn clone_to_arc(&self) -> Arc<Self> {
+        Arc::from(self)
+    }
+}
+
+impl CloneUnsized for CStr {
+    #[inline]
+    fn clone_to_box(&self) -> Box<Self> {
+        Box::from(self)
+    }
+
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    fn clone_to_arc(&self) -> Arc<Self> {
+        Arc::from(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl CloneUnsized for std::ffi::OsStr {
+    #[inline]
+    fn clone_to_box(&self) -> Box<Self> {
+        Box::from(self)
+    }
+
+    #[cfg(target_has_atomic = "ptr")]
+    #[inline]
+    fn clone_to_arc(&self) -> Arc<Self> {
+        Arc::from(self)
+    }
+}
+
+/// Assembles a boxed clone of `template` from a cloned tail.
+///
+/// `write_header` is called with a pointer to the new allocation and must
+/// write clones of every field except the tail. The tail is moved into the
+/// allocation at `tail_offset` and its box is freed without dropping it.
+///
+/// # Safety
+///
+/// `tail_offset` must be the offset of the last field of `T`, which must be
+/// of type `Tail`. `write_header` must initialize every other field of `T` at
+/// the same offsets they have in `template`.
+#[doc(hidden)]
+pub unsafe fn assemble_clone<T, Tail>(
+    template: &T,
+    tail: Box<Tail>,
+    tail_offset: usize,
+    write_header: impl FnOnce(*mut u8),
+) -> Box<T>
+where
+    T: Pointee + ?Sized,
+    Tail: Pointee<Metadata = T::Metadata> + ?Sized,
+{
+    let tail_metadata = metadata(&*tail);
+    assert!(
+        tail_metadata == metadata(template),
+        "`clone_to_box` returned a value with different metadata",
+    );
+
+    let layout = Layout::for_value(template);
+    let data_address = if layout.size() == 0 {
+        ptr::null_mut::<u8>().wrapping_add(layout.align())
+    } else {
+        // SAFETY: `layout` has a non-zero size.
+        let ptr = unsafe { alloc(layout) };
+        if ptr.is_null() {
+            handle_alloc_error(layout);
+        }
+        ptr
+    };
+
+    write_header(data_address);
+
+    let tail_layout = Layout::for_value(&*tail);
+    let tail = Box::into_raw(tail);
+    // SAFETY: The tail has the same metadata as the tail of `template`, so it
+    // fits at `tail_offset` in the new allocation. The tail's own allocation
+    // is freed without dropping its value, which has been moved.
+    unsafe {
+        ptr::copy_nonoverlapping(
+            tail.cast::<u8>(),
+            data_address.add(tail_offset),
+            tail_layout.size(),
+        );
+        if tail_layout.size() != 0 {
+            dealloc(tail.cast(), tail_layout);
+        }
+    }
+
+    // SAFETY: Every field has been initialized, and the memory was allocated
+    // by the global allocator with the layout of the value.
+    unsafe {
+        Box::from_raw(from_raw_parts_mut(data_address.cast(), tail_metadata))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, string::String};
+    use core::ffi::CStr;
+
+    use super::CloneUnsized;
+
+    #[test]
+    fn builtin_clones() {
+        let strings = [String::from("a"), String::from("b")];
+        let boxed = strings[..].clone_to_box();
+        assert_eq!(*boxed, strings);
+
+        let boxed: Box<str> = "hello".clone_to_box();
+        assert_eq!(&*boxed, "hello");
+        assert_eq!(&*"hello".clone_to_arc(), "hello");
+
+        let c: &CStr = c"hi";
+        assert_eq!(&*c.clone_to_box(), c);
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derived_clones() {
+        use alloc::borrow::{Cow, ToOwned};
+
+        use crate::{DstBuilder, Pointee};
+
+        #[derive(Pointee)]
+        #[ptr_meta(crate, clone_unsized)]
+        #[repr(C)]
+        struct Block<H, T> {
+            first: u8,
+            header: H,
+            elements: [T],
+        }
+
+        #[derive(Pointee)]
+        #[ptr_meta(crate, clone_unsized)]
+        #[repr(C)]
+        struct Name {
+            id: u64,
+            name: str,
+        }
+
+        let block: Box<Block<String, String>> =
+            DstBuilder::new((1, String::from("header")))
+                .build_from_slice(&[String::from("x"), String::from("y")]);
+        let cloned = block.clone_to_box();
+        assert_eq!(cloned.first, 1);
+        assert_eq!(cloned.header, "header");
+        assert_eq!(cloned.elements, ["x", "y"]);
+
+        let arc = block.clone_to_arc();
+        assert_eq!(arc.elements, ["x", "y"]);
+
+        let owned = block.to_owned();
+        assert_eq!(owned.header, "header");
+
+        let cow = Cow::Borrowed(&*block);
+        let owned = cow.into_owned();
+        assert_eq!(owned.elements, ["x", "y"]);
+
+        let empty: Box<Block<(), ()>> =
+            DstBuilder::new((0, ())).build_from_slice(&[]);
+        assert_eq!(empty.clone_to_box().elements.len(), 0);
+
+        let name: Box<Name> = DstBuilder::new(7).build_from_str("hello");
+        let cloned = name.clone_to_box();
+        assert_eq!(cloned.id, 7);
+        assert_eq!(&cloned.name, "hello");
+    }
+}
@@ -0,0 +1,295 @@
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+
+use crate::{from_raw_parts_mut, to_raw_parts_mut, Pointee};
+
+/// An unsigned integer type which can store the length of a [`CompactPtr`].
+///
+/// This is implemented for `u8`, `u16`, and `usize` on all targets, `u32` on
+/// 32-bit and 64-bit targets, and `u64` on 64-bit targets. It is only
+/// implemented for types no wider than `usize`, so widening with `to_len` is
+/// always lossless.
+pub trait CompactLen: Copy + Eq + Hash + fmt::Debug {
+    /// Narrows a length to this type, returning `None` if it does not fit.
+    fn from_len(len: usize) -> Option<Self>;
+
+    /// Widens this value back to a length.
+    fn to_len(self) -> usize;
+}
+
+macro_rules! impl_compact_len {
+    ($($ty:ty),*) => {
+        $(
+            impl CompactLen for $ty {
+                #[inline]
+                fn from_len(len: usize) -> Option<Self> {
+                    Self::try_from(len).ok()
+                }
+
+                #[inline]
+                fn to_len(self) -> usize {
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_compact_len!(u8, u16, usize);
+#[cfg(any(target_pointer_width = "32", target_pointer_width = "64"))]
+impl_compact_len!(u32);
+#[cfg(target_pointer_width = "64")]
+impl_compact_len!(u64);
+
+/// A pointer to a slice-like type which stores its length in fewer bits.
+///
+/// `CompactPtr<T, L>` can point to any type whose metadata is a `usize`
+/// length, such as slices, `str`, `CStr`, and structs with a slice tail
+/// deriving `Pointee`. The length is stored as an `L`, which is `u32` by
+/// default and may be any other [`CompactLen`]. On 16-bit targets, `u32` is
+/// not a `CompactLen` and a smaller length must be chosen.
+///
+/// The pointer is packed, so a `CompactPtr<[T]>` takes 12 bytes on 64-bit
+/// targets instead of the 16 taken by a `*const [T]`.
+///
+/// Lengths are checked when narrowed during construction and widened
+/// losslessly when the pointer is converted back with [`from_raw_parts`].
+///
+/// [`from_raw_parts`]: crate::from_raw_parts
+///
+/// # Example
+///
+/// ```
+/// use core::mem::size_of;
+///
+/// use ptr_meta::CompactPtr;
+///
+/// let values = [1u64, 2, 3];
+/// let compact = CompactPtr::<[u64], u16>::new(&values[..]);
+/// assert_eq!(compact.len(), 3);
+/// assert_eq!(unsafe { &*compact.as_ptr() }, &[1, 2, 3]);
+///
+/// assert!(size_of::<CompactPtr<[u64], u16>>() < size_of::<*const [u64]>());
+/// ```
+#[repr(C, packed)]
+pub struct CompactPtr<T, L = u32>
+where
+    T: Pointee<Metadata = usize> + ?Sized,
+    L: CompactLen,
+{
+    data_address: *mut (),
+    len: L,
+    _phantom: PhantomData<*mut T>,
+}
+
+impl<T, L> CompactPtr<T, L>
+where
+    T: Pointee<Metadata = usize> + ?Sized,
+    L: CompactLen,
+{
+    /// Returns a new compact pointer from a pointer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `ptr` does not fit in `L`.
+    #[inline]
+    pub fn new(ptr: *const T) -> Self {
+        Self::try_new(ptr).expect("the length must fit in the length type")
+    }
+
+    /// Returns a new compact pointer from a pointer, or `None` if the length
+    /// of `ptr` does not fit in `L`.
+    #[inline]
+    pub fn try_new(ptr: *const T) -> Option<Self> {
+        let (data_address, len) = to_raw_parts_mut(ptr.cast_mut());
+        Some(Self::from_raw_parts(data_address, L::from_len(len)?))
+    }
+
+    /// Returns a new compact pointer from its data address and length.
+    #[inline]
+    pub fn from_raw_parts(data_address: *mut (), len: L) -> Self {
+        Self {
+            data_address,
+            len,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the data address of the pointer.
+    #[inline]
+    pub fn data_address(self) -> *mut () {
+        self.data_address
+    }
+
+    /// Returns the length stored in the pointer.
+    #[inline]
+    pub fn len(self) -> usize {
+        let len = self.len;
+        len.to_len()
+    }
+
+    /// Returns whether the length stored in the pointer is zero.
+    #[inline]
+    pub fn is_empty(self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the full-width pointer.
+    #[inline]
+    pub fn as_ptr(self) -> *const T {
+        self.as_mut_ptr()
+    }
+
+    /// Returns the full-width mutable pointer.
+    #[inline]
+    pub fn as_mut_ptr(self) -> *mut T {
+        from_raw_parts_mut(self.data_address, self.len())
+    }
+}
+
+// Manual impls needed to avoid `T: $Trait` bounds.
+
+impl<T, L> Copy for CompactPtr<T, L>
+where
+    T: Pointee<Metadata = usize> + ?Sized,
+    L: CompactLen,
+{
+}
+
+impl<T, L> Clone for CompactPtr<T, L>
+where
+    T: Pointee<Metadata = usize> + ?Sized,
+    L: CompactLen,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, L> Eq for CompactPtr<T, L>
+where
+    T: Pointee<Metadata = usize> + ?Sized,
+    L: CompactLen,
+{
+}
+
+impl<T, L> PartialEq for CompactPtr<T, L>
+where
+    T: Pointee<Metadata = usize> + ?Sized,
+    L: CompactLen,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.data_address() == other.data_address() && self.len() == other.len()
+    }
+}
+
+impl<T, L> Hash for CompactPtr<T, L>
+where
+    T: Pointee<Metadata = usize> + ?Sized,
+    L: CompactLen,
+{
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.data_address().hash(state);
+        self.len().hash(state);
+    }
+}
+
+impl<T, L> fmt::Debug for CompactPtr<T, L>
+where
+    T: Pointee<Metadata = usize> + ?Sized,
+    L: CompactLen,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompactPtr")
+            .field("data_address", &self.data_address())
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{ffi::CStr, mem::size_of, ptr};
+
+    use super::CompactPtr;
+
+    #[test]
+    fn round_trip() {
+        let values = [1u32, 2, 3, 4];
+        let compact = CompactPtr::<[u32]>::new(&values[..]);
+        assert_eq!(compact.len(), 4);
+        assert!(ptr::eq(compact.as_ptr(), &values[..]));
+
+        let string = "hello";
+        let compact = CompactPtr::<str, u16>::new(string);
+        // SAFETY: `compact` points to `string`.
+        assert_eq!(unsafe { &*compact.as_ptr() }, "hello");
+
+        let c: &CStr = c"hi";
+        let compact = CompactPtr::<CStr>::new(c);
+        // SAFETY: `compact` points to `c`.
+        assert_eq!(unsafe { &*compact.as_ptr() }, c);
+
+        let empty = CompactPtr::<[u8], u16>::new(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(empty, CompactPtr::from_raw_parts(empty.data_address(), 0));
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derived_slice_tail() {
+        use crate::{from_raw_parts, Pointee};
+
+        #[derive(Pointee)]
+        #[ptr_meta(crate)]
+        #[repr(C)]
+        struct Block {
+            header: u32,
+            elements: [u16],
+        }
+
+        let values = [5u32, 0x0001_0002];
+        let block =
+            from_raw_parts::<Block>(values.as_ptr().cast(), 2).cast_mut();
+        let compact = CompactPtr::<Block, u16>::new(block);
+        assert_eq!(compact.len(), 2);
+        // SAFETY: `compact` points to `values`, which has the layout of a
+        // `Block` with two elements.
+        let block = unsafe { &*compact.as_ptr() };
+        assert_eq!(block.header, 5);
+        assert_eq!(block.elements.len(), 2);
+    }
+
+    #[test]
+    fn checked_narrowing() {
+        let long = ptr::slice_from_raw_parts(ptr::null::<()>(), 1 << 16);
+        assert!(CompactPtr::<[()], u16>::try_new(long).is_none());
+        assert_eq!(
+            CompactPtr::<[()], u32>::try_new(long).unwrap().len(),
+            1 << 16
+        );
+
+        let max = ptr::slice_from_raw_parts(ptr::null::<()>(), u16::MAX.into());
+        assert!(CompactPtr::<[()], u16>::try_new(max).is_some());
+    }
+
+    #[test]
+    fn packed_sizes() {
+        let word = size_of::<usize>();
+        assert_eq!(size_of::<CompactPtr<[u64]>>(), word + 4);
+        assert_eq!(size_of::<CompactPtr<str, u16>>(), word + 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_long() {
+        let long = ptr::slice_from_raw_parts(ptr::null::<()>(), 1 << 16);
+        CompactPtr::<[()], u16>::new(long);
+    }
+}
@@ -0,0 +1,177 @@
+#[cfg(feature = "alloc")]
+use alloc::{alloc::dealloc, boxed::Box};
+#[cfg(feature = "alloc")]
+use core::{alloc::Layout, mem::MaybeUninit};
+use core::{mem::size_of_val, ptr};
+
+use crate::{from_raw_parts_mut, metadata, Pointee};
+
+/// Copies the bytes of the value pointed to by `src` to `dst` and returns a
+/// pointer to the copy.
+///
+/// Exactly as many bytes as the metadata of `src` describes are copied. The
+/// returned pointer has the data address `dst` and the same metadata as
+/// `src`.
+///
+/// Like [`ptr::read`], this creates a bitwise copy of the value. Only one of
+/// the two values may be used or dropped afterward unless `T` is `Copy`.
+///
+/// # Safety
+///
+/// - `src` must be aligned and point to a valid value of `T`.
+/// - `dst` must be valid for writes of `size_of_val(&*src)` bytes and aligned
+///   to `align_of_val(&*src)`.
+/// - The source and destination must not overlap.
+///
+/// # Example
+///
+/// ```
+/// use core::mem::MaybeUninit;
+///
+/// use ptr_meta::copy_unsized;
+///
+/// let source: &str = "hello";
+/// let mut buffer = [MaybeUninit::<u8>::uninit(); 8];
+/// let copy = unsafe { copy_unsized(source, buffer.as_mut_ptr().cast()) };
+/// assert_eq!(unsafe { &*copy }, "hello");
+/// ```
+pub unsafe fn copy_unsized<T: Pointee + ?Sized>(
+    src: *const T,
+    dst: *mut u8,
+) -> *mut T {
+    // SAFETY: The caller has guaranteed that `src` points to a valid `T`.
+    let size = size_of_val(unsafe { &*src });
+    // SAFETY: The caller has guaranteed that `src` is valid for reads of
+    // `size` bytes, that `dst` is valid for writes of `size` bytes, and that
+    // they do not overlap.
+    unsafe {
+        ptr::copy_nonoverlapping(src.cast::<u8>(), dst, size);
+    }
+    from_raw_parts_mut(dst.cast(), metadata(src))
+}
+
+/// Moves the value out of `boxed` and into `buffer`, then frees the box
+/// without dropping the value.
+///
+/// Returns a reference to the moved value at the start of `buffer`, or `None`
+/// if `buffer` is too small or not aligned for the value. When `None` is
+/// returned, the box is dropped normally.
+///
+/// The returned reference owns the value, which will not be dropped unless
+/// the caller drops it in place.
+///
+/// # Example
+///
+/// ```
+/// use core::{any::Any, mem::MaybeUninit};
+///
+/// use ptr_meta::move_out_of_box;
+///
+/// let boxed: Box<dyn Any> = Box::new(42u32);
+/// let mut buffer = [MaybeUninit::<u32>::uninit(); 2];
+/// let bytes = unsafe {
+///     core::slice::from_raw_parts_mut(
+///         buffer.as_mut_ptr().cast::<MaybeUninit<u8>>(),
+///         8,
+///     )
+/// };
+///
+/// let moved = move_out_of_box(boxed, bytes).unwrap();
+/// assert_eq!(moved.downcast_ref::<u32>(), Some(&42));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn move_out_of_box<T: Pointee + ?Sized>(
+    boxed: Box<T>,
+    buffer: &mut [MaybeUninit<u8>],
+) -> Option<&mut T> {
+    let layout = Layout::for_value(&*boxed);
+    let dst = buffer.as_mut_ptr().cast::<u8>();
+    if layout.size() > buffer.len() || dst as usize & (layout.align() - 1) != 0
+    {
+        return None;
+    }
+
+    let src = Box::into_raw(boxed);
+    // SAFETY: `src` points to a valid `T`. `buffer` is large enough and
+    // aligned for it, and cannot overlap the box because it is borrowed
+    // mutably.
+    let moved = unsafe { copy_unsized(src, dst) };
+    if layout.size() != 0 {
+        // SAFETY: `src` was allocated by the global allocator with `layout`.
+        // The value has been moved, so it is freed without being dropped.
+        unsafe {
+            dealloc(src.cast(), layout);
+        }
+    }
+
+    // SAFETY: `moved` points to the valid value which was moved into
+    // `buffer`, which is borrowed for the returned lifetime.
+    Some(unsafe { &mut *moved })
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use alloc::{boxed::Box, rc::Rc, string::String, vec::Vec};
+    use core::{cell::Cell, mem::MaybeUninit, ptr};
+
+    use super::{copy_unsized, move_out_of_box};
+
+    fn bytes_of<T>(buffer: &mut [MaybeUninit<T>]) -> &mut [MaybeUninit<u8>] {
+        let len = core::mem::size_of_val(buffer);
+        // SAFETY: `buffer` is valid for reads and writes of `len` bytes, which
+        // may be uninitialized.
+        unsafe {
+            core::slice::from_raw_parts_mut(buffer.as_mut_ptr().cast(), len)
+        }
+    }
+
+    #[test]
+    fn copy_between_buffers() {
+        let source = [1u16, 2, 3];
+        let mut buffer = [MaybeUninit::<u16>::uninit(); 4];
+        // SAFETY: `buffer` is large enough and aligned for three `u16`s.
+        let copy =
+            unsafe { copy_unsized(&source[..], buffer.as_mut_ptr().cast()) };
+        assert_eq!(copy.len(), 3);
+        // SAFETY: `copy` points to three initialized `u16`s.
+        assert_eq!(unsafe { &*copy }, &source);
+        assert!(ptr::eq(copy.cast::<u16>(), buffer.as_ptr().cast()));
+    }
+
+    #[test]
+    fn move_without_drop() {
+        struct Tracked(Rc<Cell<usize>>);
+
+        impl Drop for Tracked {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Rc::new(Cell::new(0));
+        let boxed: Box<[Tracked]> =
+            (0..2).map(|_| Tracked(drops.clone())).collect();
+        let mut buffer = [MaybeUninit::<usize>::uninit(); 2];
+        let moved = move_out_of_box(boxed, bytes_of(&mut buffer)).unwrap();
+        assert_eq!(moved.len(), 2);
+        assert_eq!(drops.get(), 0);
+
+        // SAFETY: `moved` owns the values, which are not used again.
+        unsafe {
+            ptr::drop_in_place(moved);
+        }
+        assert_eq!(drops.get(), 2);
+
+        let boxed: Box<str> = String::from("too long").into_boxed_str();
+        let mut small = [MaybeUninit::<u8>::uninit(); 4];
+        assert!(move_out_of_box(boxed, &mut small).is_none());
+
+        let boxed: Box<[u32]> = Vec::from([1, 2]).into_boxed_slice();
+        let mut buffer = [MaybeUninit::<u32>::uninit(); 3];
+        let misaligned = &mut bytes_of(&mut buffer)[1..];
+        assert!(move_out_of_box(boxed, misaligned).is_none());
+
+        let empty: Box<[u8]> = Box::new([]);
+        assert_eq!(move_out_of_box(empty, &mut []).unwrap().len(), 0);
+    }
+}
@@ -0,0 +1,467 @@
+//! A bump allocator which owns and drops values of unsized types.
+
+use alloc::{
+    alloc::{alloc, dealloc, handle_alloc_error},
+    vec::Vec,
+};
+use core::{
+    alloc::Layout,
+    cell::{Cell, RefCell},
+    fmt,
+    marker::PhantomData,
+    mem::needs_drop,
+    ptr::{self, NonNull},
+};
+
+use crate::{from_raw_parts_mut, metadata, Pointee, Unsize};
+
+// The size of the first chunk allocated by an arena.
+const MIN_CHUNK_SIZE: usize = 1024;
+// The minimum alignment of every chunk.
+const CHUNK_ALIGN: usize = 16;
+
+// The type-erased part of a drop record. Drop records are allocated inside of
+// the arena and form a singly-linked list from most to least recently
+// allocated.
+struct DropHeader {
+    next: *mut DropHeader,
+    drop: unsafe fn(*mut DropHeader),
+}
+
+#[repr(C)]
+struct DropNode<T: Pointee + ?Sized> {
+    header: DropHeader,
+    data_address: *mut (),
+    metadata: <T as Pointee>::Metadata,
+}
+
+// SAFETY: `header` must point to the header of a `DropNode<T>` which describes
+// a live value.
+unsafe fn drop_node<T: Pointee + ?Sized>(header: *mut DropHeader) {
+    let node = header.cast::<DropNode<T>>();
+    // SAFETY: The caller has guaranteed that `header` is the first field of a
+    // `#[repr(C)]` `DropNode<T>`.
+    let (data_address, metadata) =
+        unsafe { ((*node).data_address, (*node).metadata) };
+    // SAFETY: The caller has guaranteed that the node describes a live value.
+    unsafe {
+        ptr::drop_in_place(from_raw_parts_mut::<T>(data_address, metadata));
+    }
+}
+
+/// A bump allocator for values of any type, including trait objects and
+/// slices.
+///
+/// Values are moved into large chunks of memory owned by the arena. Each
+/// allocation returns a mutable reference which lives as long as the shared
+/// borrow of the arena, so many values can be allocated and used at once.
+/// Unlike most bump allocators, `DynArena` runs the drop glue for each of its
+/// values when it is [reset](DynArena::reset) or dropped. To do so, it records
+/// the [`Pointee`] metadata of every value which needs to be dropped.
+///
+/// The lifetime `'a` bounds the values which may be stored in the arena. It is
+/// usually inferred.
+///
+/// # Example
+///
+/// ```
+/// use ptr_meta::DynArena;
+///
+/// #[ptr_meta::pointee]
+/// trait Task {
+///     fn run(&mut self) -> u32;
+/// }
+///
+/// struct Add(u32, u32);
+///
+/// impl Task for Add {
+///     fn run(&mut self) -> u32 {
+///         self.0 + self.1
+///     }
+/// }
+///
+/// let arena = DynArena::new();
+/// let task: &mut dyn Task = arena.alloc_dyn(Add(1, 2));
+/// assert_eq!(task.run(), 3);
+///
+/// let name: &mut str = arena.alloc_str("hello");
+/// name.make_ascii_uppercase();
+/// assert_eq!(name, "HELLO");
+/// ```
+pub struct DynArena<'a> {
+    ptr: Cell<*mut u8>,
+    remaining: Cell<usize>,
+    drops: Cell<*mut DropHeader>,
+    chunks: RefCell<Vec<(NonNull<u8>, Layout)>>,
+    _phantom: PhantomData<Cell<&'a ()>>,
+}
+
+// Every allocation returns a reference to a distinct region of the arena.
+#[allow(clippy::mut_from_ref)]
+impl<'a> DynArena<'a> {
+    /// Returns a new, empty arena.
+    ///
+    /// This does not allocate until the first value is allocated.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            ptr: Cell::new(ptr::null_mut()),
+            remaining: Cell::new(0),
+            drops: Cell::new(ptr::null_mut()),
+            chunks: RefCell::new(Vec::new()),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the total number of bytes allocated by the arena for its
+    /// chunks.
+    pub fn allocated_bytes(&self) -> usize {
+        self.chunks
+            .borrow()
+            .iter()
+            .map(|(_, layout)| layout.size())
+            .sum()
+    }
+
+    /// Moves `value` into the arena and returns a mutable reference to it.
+    #[inline]
+    pub fn alloc<U: 'a>(&self, value: U) -> &mut U {
+        // SAFETY: `()` is the metadata of every sized type.
+        unsafe { self.alloc_with_metadata(value, ()) }
+    }
+
+    /// Moves `value` into the arena and returns it as a mutable reference to
+    /// the unsized type `T`.
+    #[inline]
+    pub fn alloc_dyn<T, U>(&self, value: U) -> &mut T
+    where
+        T: Pointee + ?Sized + 'a,
+        U: Unsize<T> + 'a,
+    {
+        // SAFETY: `U: Unsize<T>` guarantees that the unsize metadata makes a
+        // pointer to a `U` into a valid pointer to a `T`.
+        unsafe { self.alloc_with_metadata(value, U::unsize_metadata()) }
+    }
+
+    // SAFETY: A pointer to `value` with `metadata` must be a valid pointer to
+    // `T`.
+    unsafe fn alloc_with_metadata<T, U>(
+        &self,
+        value: U,
+        metadata: T::Metadata,
+    ) -> &mut T
+    where
+        T: Pointee + ?Sized + 'a,
+        U: 'a,
+    {
+        let data = self.alloc_layout(Layout::new::<U>()).cast::<U>();
+        // SAFETY: `data` is valid for writes and properly aligned for `U`.
+        unsafe {
+            data.as_ptr().write(value);
+        }
+        let ptr = from_raw_parts_mut::<T>(data.as_ptr().cast(), metadata);
+        if needs_drop::<U>() {
+            // SAFETY: The caller has guaranteed that `ptr` is a valid pointer
+            // to `T`, and it points to a live value which is not yet
+            // registered.
+            unsafe {
+                self.register_drop(ptr);
+            }
+        }
+        // SAFETY: `ptr` points to a live value which is only reachable
+        // through the returned reference.
+        unsafe { &mut *ptr }
+    }
+
+    /// Clones the elements of `slice` into the arena and returns a mutable
+    /// reference to the copy.
+    pub fn alloc_slice_clone<E: Clone + 'a>(&self, slice: &[E]) -> &mut [E] {
+        let layout = Layout::for_value(slice);
+        let data = self.alloc_layout(layout).cast::<E>();
+
+        // Drops the already-cloned elements if a clone panics.
+        struct Guard<E> {
+            data: *mut E,
+            len: usize,
+        }
+
+        impl<E> Drop for Guard<E> {
+            fn drop(&mut self) {
+                // SAFETY: The first `len` elements have been initialized.
+                unsafe {
+                    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                        self.data, self.len,
+                    ));
+                }
+            }
+        }
+
+        let mut guard = Guard {
+            data: data.as_ptr(),
+            len: 0,
+        };
+        for element in slice.iter() {
+            // SAFETY: `data` has room for `slice.len()` elements, and fewer
+            // than that have been written so far.
+            unsafe {
+                guard.data.add(guard.len).write(element.clone());
+            }
+            guard.len += 1;
+        }
+        core::mem::forget(guard);
+
+        let ptr = ptr::slice_from_raw_parts_mut(data.as_ptr(), slice.len());
+        if needs_drop::<E>() {
+            // SAFETY: `ptr` points to a live value which is not yet
+            // registered.
+            unsafe {
+                self.register_drop(ptr);
+            }
+        }
+        // SAFETY: `ptr` points to a live value which is only reachable
+        // through the returned reference.
+        unsafe { &mut *ptr }
+    }
+
+    /// Copies the elements of `slice` into the arena and returns a mutable
+    /// reference to the copy.
+    pub fn alloc_slice_copy<E: Copy + 'a>(&self, slice: &[E]) -> &mut [E] {
+        let data = self.alloc_layout(Layout::for_value(slice)).cast::<E>();
+        // SAFETY: `data` has room for `slice.len()` elements and cannot
+        // overlap with `slice`.
+        unsafe {
+            ptr::copy_nonoverlapping(
+                slice.as_ptr(),
+                data.as_ptr(),
+                slice.len(),
+            );
+            &mut *ptr::slice_from_raw_parts_mut(data.as_ptr(), slice.len())
+        }
+    }
+
+    /// Copies `s` into the arena and returns a mutable reference to the copy.
+    pub fn alloc_str(&self, s: &str) -> &mut str {
+        let bytes = self.alloc_slice_copy(s.as_bytes());
+        // SAFETY: `bytes` was copied from a valid `str`.
+        unsafe { &mut *from_raw_parts_mut(bytes.as_mut_ptr().cast(), s.len()) }
+    }
+
+    /// Drops every value in the arena and makes its memory available for
+    /// reuse.
+    ///
+    /// Values are dropped in the reverse of the order they were allocated.
+    /// Only the most recently allocated chunk is retained.
+    pub fn reset(&mut self) {
+        self.run_drops();
+
+        let chunks = self.chunks.get_mut();
+        if let Some(last) = chunks.pop() {
+            for (ptr, layout) in chunks.drain(..) {
+                // SAFETY: Each chunk was allocated with its layout.
+                unsafe {
+                    dealloc(ptr.as_ptr(), layout);
+                }
+            }
+            self.ptr.set(last.0.as_ptr());
+            self.remaining.set(last.1.size());
+            chunks.push(last);
+        }
+    }
+
+    fn run_drops(&mut self) {
+        let mut head = self.drops.replace(ptr::null_mut());
+        while !head.is_null() {
+            // SAFETY: Every non-null drop record pointer points to a valid
+            // drop record. The record is unlinked before its value is dropped
+            // so that a panicking drop does not cause a double drop.
+            unsafe {
+                let DropHeader { next, drop } = head.read();
+                self.drops.set(next);
+                drop(head);
+            }
+            head = self.drops.replace(ptr::null_mut());
+        }
+    }
+
+    // SAFETY: `ptr` must point to a live value in the arena which is not yet
+    // registered for dropping.
+    unsafe fn register_drop<T: Pointee + ?Sized>(&self, ptr: *mut T) {
+        let node = self
+            .alloc_layout(Layout::new::<DropNode<T>>())
+            .cast::<DropNode<T>>();
+        let (data_address, metadata) = (ptr.cast::<()>(), metadata(ptr));
+        // SAFETY: `node` is valid for writes and properly aligned.
+        unsafe {
+            node.as_ptr().write(DropNode {
+                header: DropHeader {
+                    next: self.drops.get(),
+                    drop: drop_node::<T>,
+                },
+                data_address,
+                metadata,
+            });
+        }
+        self.drops.set(node.as_ptr().cast());
+    }
+
+    fn alloc_layout(&self, layout: Layout) -> NonNull<u8> {
+        let ptr = self.ptr.get();
+        let remaining = self.remaining.get();
+        if !ptr.is_null() {
+            let offset = ptr.align_offset(layout.align());
+            if offset <= remaining && layout.size() <= remaining - offset {
+                self.remaining.set(remaining - offset - layout.size());
+                // SAFETY: `offset + layout.size()` bytes remain in the current
+                // chunk.
+                unsafe {
+                    let result = ptr.add(offset);
+                    self.ptr.set(result.add(layout.size()));
+                    return NonNull::new_unchecked(result);
+                }
+            }
+        }
+
+        self.alloc_chunk(layout)
+    }
+
+    #[cold]
+    fn alloc_chunk(&self, layout: Layout) -> NonNull<u8> {
+        let mut chunks = self.chunks.borrow_mut();
+        let last_size = chunks.last().map_or(0, |(_, layout)| layout.size());
+        let size = layout
+            .size()
+            .max(last_size.saturating_mul(2))
+            .max(MIN_CHUNK_SIZE);
+        let chunk_layout =
+            Layout::from_size_align(size, layout.align().max(CHUNK_ALIGN))
+                .expect("arena chunk too large");
+
+        // SAFETY: `chunk_layout` has a non-zero size.
+        let chunk = unsafe { alloc(chunk_layout) };
+        let chunk = NonNull::new(chunk)
+            .unwrap_or_else(|| handle_alloc_error(chunk_layout));
+        chunks.push((chunk, chunk_layout));
+
+        // SAFETY: The chunk is at least `layout.size()` bytes long.
+        self.ptr.set(unsafe { chunk.as_ptr().add(layout.size()) });
+        self.remaining.set(size - layout.size());
+        chunk
+    }
+}
+
+impl Drop for DynArena<'_> {
+    fn drop(&mut self) {
+        self.run_drops();
+        for (ptr, layout) in self.chunks.get_mut().drain(..) {
+            // SAFETY: Each chunk was allocated with its layout.
+            unsafe {
+                dealloc(ptr.as_ptr(), layout);
+            }
+        }
+    }
+}
+
+impl Default for DynArena<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for DynArena<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynArena")
+            .field("allocated_bytes", &self.allocated_bytes())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use alloc::{rc::Rc, string::String, vec::Vec};
+    use core::cell::{Cell, RefCell};
+
+    use super::DynArena;
+
+    #[crate::pointee(crate)]
+    trait Named {
+        fn name(&self) -> &str;
+    }
+
+    struct Logged<'a> {
+        name: &'static str,
+        log: &'a RefCell<Vec<&'static str>>,
+    }
+
+    impl Named for Logged<'_> {
+        fn name(&self) -> &str {
+            self.name
+        }
+    }
+
+    impl Drop for Logged<'_> {
+        fn drop(&mut self) {
+            self.log.borrow_mut().push(self.name);
+        }
+    }
+
+    #[repr(align(64))]
+    struct Aligned;
+
+    impl Named for Aligned {
+        fn name(&self) -> &str {
+            "aligned"
+        }
+    }
+
+    #[test]
+    fn alloc_dyn_and_drop() {
+        let log = RefCell::new(Vec::new());
+        {
+            let arena = DynArena::new();
+            let a: &mut dyn Named = arena.alloc_dyn(Logged {
+                name: "a",
+                log: &log,
+            });
+            let b: &mut dyn Named = arena.alloc_dyn(Aligned);
+            let c: &mut dyn Named = arena.alloc_dyn(Logged {
+                name: "c",
+                log: &log,
+            });
+
+            assert_eq!(a.name(), "a");
+            assert_eq!(b.name(), "aligned");
+            assert_eq!((b as *mut dyn Named).cast::<u8>() as usize % 64, 0);
+            assert_eq!(c.name(), "c");
+            assert!(log.borrow().is_empty());
+        }
+        assert_eq!(*log.borrow(), ["c", "a"]);
+    }
+
+    #[test]
+    fn slices_and_reset() {
+        let counter = Rc::new(Cell::new(0));
+        let mut arena = DynArena::new();
+
+        for _ in 0..3 {
+            let values = [counter.clone(), counter.clone()];
+            let slice = arena.alloc_slice_clone(&values);
+            assert_eq!(slice.len(), 2);
+            assert_eq!(Rc::strong_count(&counter), 5);
+            drop(values);
+
+            let unsized_array: &mut [u32] = arena.alloc_dyn([1, 2, 3]);
+            assert_eq!(unsized_array, [1, 2, 3]);
+
+            let big = arena.alloc_slice_copy(&[0u8; 4096]);
+            assert_eq!(big.len(), 4096);
+
+            let s = arena.alloc(String::from("owned"));
+            s.push('!');
+            assert_eq!(s, "owned!");
+
+            arena.reset();
+            assert_eq!(Rc::strong_count(&counter), 1);
+        }
+    }
+}
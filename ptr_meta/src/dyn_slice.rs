@@ -0,0 +1,342 @@
+//! Slices of a single concrete type viewed as trait objects.
+
+use core::{
+    fmt,
+    iter::FusedIterator,
+    marker::PhantomData,
+    ops::{Bound, Index, RangeBounds},
+};
+
+use crate::{from_raw_parts, DynMetadata, Pointee, Unsize};
+
+/// A slice of values of a single concrete type viewed as trait objects.
+///
+/// A `DynSlice<'a, dyn Trait>` is created from a `&'a [Concrete]` and yields
+/// `&'a dyn Trait`s. Instead of storing one wide pointer per element like
+/// `Vec<&dyn Trait>`, it stores the data address, the length, and a single
+/// [`DynMetadata`] which is shared by every element. The size from that
+/// metadata is used as the stride between elements.
+///
+/// # Example
+///
+/// ```
+/// use ptr_meta::DynSlice;
+///
+/// #[ptr_meta::pointee]
+/// trait Describe {
+///     fn describe(&self) -> String;
+/// }
+///
+/// impl Describe for u16 {
+///     fn describe(&self) -> String {
+///         format!("u16 {self}")
+///     }
+/// }
+///
+/// let values = [1u16, 2, 3, 4];
+/// let dyn_slice = DynSlice::<dyn Describe>::new(&values);
+///
+/// assert_eq!(dyn_slice.len(), 4);
+/// assert_eq!(dyn_slice[1].describe(), "u16 2");
+///
+/// let tail = dyn_slice.slice(2..);
+/// let described = tail.iter().map(|d| d.describe()).collect::<Vec<_>>();
+/// assert_eq!(described, ["u16 3", "u16 4"]);
+/// ```
+pub struct DynSlice<'a, T: Pointee<Metadata = DynMetadata<T>> + ?Sized> {
+    data_address: *const (),
+    len: usize,
+    metadata: DynMetadata<T>,
+    _phantom: PhantomData<&'a T>,
+}
+
+// SAFETY: `DynSlice` behaves like a slice of shared references to `T`.
+unsafe impl<T> Send for DynSlice<'_, T> where
+    T: Pointee<Metadata = DynMetadata<T>> + Sync + ?Sized
+{
+}
+
+// SAFETY: `DynSlice` behaves like a slice of shared references to `T`.
+unsafe impl<T> Sync for DynSlice<'_, T> where
+    T: Pointee<Metadata = DynMetadata<T>> + Sync + ?Sized
+{
+}
+
+impl<'a, T: Pointee<Metadata = DynMetadata<T>> + ?Sized> DynSlice<'a, T> {
+    /// Returns a `DynSlice` over the elements of `slice`.
+    #[inline]
+    pub fn new<U: Unsize<T>>(slice: &'a [U]) -> Self {
+        Self {
+            data_address: slice.as_ptr().cast(),
+            len: slice.len(),
+            metadata: U::unsize_metadata(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns a `DynSlice` from its data address, length, and metadata.
+    ///
+    /// # Safety
+    ///
+    /// `data_address` must point to `len` consecutive values of the type
+    /// described by `metadata`, which must be valid for reads for `'a`.
+    #[inline]
+    pub unsafe fn from_raw_parts(
+        data_address: *const (),
+        len: usize,
+        metadata: DynMetadata<T>,
+    ) -> Self {
+        Self {
+            data_address,
+            len,
+            metadata,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the number of elements in the slice.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the slice is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the data address of the slice.
+    #[inline]
+    pub fn as_ptr(&self) -> *const () {
+        self.data_address
+    }
+
+    /// Returns the metadata shared by the elements of the slice.
+    #[inline]
+    pub fn metadata(&self) -> DynMetadata<T> {
+        self.metadata
+    }
+
+    /// Returns a reference to the element at `index`, or `None` if it is out
+    /// of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&'a T> {
+        if index >= self.len {
+            return None;
+        }
+        let address = self
+            .data_address
+            .cast::<u8>()
+            .wrapping_add(index * self.metadata.size_of());
+        // SAFETY: `index` is in bounds, and each element is `size_of` bytes
+        // after the previous one.
+        Some(unsafe { &*from_raw_parts(address.cast(), self.metadata) })
+    }
+
+    /// Returns the elements in `range` as a new `DynSlice`, or `None` if the
+    /// range is out of bounds.
+    pub fn get_slice(&self, range: impl RangeBounds<usize>) -> Option<Self> {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.checked_add(1)?,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end.checked_add(1)?,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len,
+        };
+        if start > end || end > self.len {
+            return None;
+        }
+
+        let stride = self.metadata.size_of();
+        Some(Self {
+            data_address: self
+                .data_address
+                .cast::<u8>()
+                .wrapping_add(start * stride)
+                .cast(),
+            len: end - start,
+            metadata: self.metadata,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns the elements in `range` as a new `DynSlice`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    #[inline]
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Self {
+        self.get_slice(range)
+            .expect("range out of bounds for `DynSlice`")
+    }
+
+    /// Returns an iterator over the elements of the slice.
+    #[inline]
+    pub fn iter(&self) -> DynSliceIter<'a, T> {
+        DynSliceIter { slice: *self }
+    }
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> Copy for DynSlice<'_, T> {}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> Clone for DynSlice<'_, T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> fmt::Debug for DynSlice<'_, T>
+where
+    T: Pointee<Metadata = DynMetadata<T>> + fmt::Debug + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> Index<usize>
+    for DynSlice<'_, T>
+{
+    type Output = T;
+
+    #[inline]
+    fn index(&self, index: usize) -> &T {
+        let len = self.len;
+        self.get(index).unwrap_or_else(|| {
+            panic!(
+                "index out of bounds: the len is {len} but the index is \
+                 {index}"
+            )
+        })
+    }
+}
+
+impl<'a, T: Pointee<Metadata = DynMetadata<T>> + ?Sized> IntoIterator
+    for DynSlice<'a, T>
+{
+    type Item = &'a T;
+    type IntoIter = DynSliceIter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the elements of a [`DynSlice`].
+pub struct DynSliceIter<'a, T: Pointee<Metadata = DynMetadata<T>> + ?Sized> {
+    slice: DynSlice<'a, T>,
+}
+
+impl<'a, T: Pointee<Metadata = DynMetadata<T>> + ?Sized> Iterator
+    for DynSliceIter<'a, T>
+{
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = self.slice.get(0)?;
+        self.slice = self.slice.slice(1..);
+        Some(first)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.slice.len, Some(self.slice.len))
+    }
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> DoubleEndedIterator
+    for DynSliceIter<'_, T>
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let last = self.slice.get(self.slice.len.checked_sub(1)?)?;
+        self.slice.len -= 1;
+        Some(last)
+    }
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> ExactSizeIterator
+    for DynSliceIter<'_, T>
+{
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> FusedIterator
+    for DynSliceIter<'_, T>
+{
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> Clone
+    for DynSliceIter<'_, T>
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        Self { slice: self.slice }
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use core::alloc::Layout;
+
+    use super::DynSlice;
+
+    #[crate::pointee(crate)]
+    trait Value {
+        fn value(&self) -> u32;
+    }
+
+    impl Value for (u8, u32) {
+        fn value(&self) -> u32 {
+            self.0 as u32 + self.1
+        }
+    }
+
+    impl Value for () {
+        fn value(&self) -> u32 {
+            7
+        }
+    }
+
+    #[test]
+    fn index_and_iterate() {
+        let values = [(1u8, 10u32), (2, 20), (3, 30)];
+        let slice = DynSlice::<dyn Value>::new(&values);
+
+        assert_eq!(slice.len(), 3);
+        assert_eq!(slice[0].value(), 11);
+        assert_eq!(slice[2].value(), 33);
+        assert!(slice.get(3).is_none());
+
+        let forward = slice.iter().map(|v| v.value());
+        assert!(forward.eq([11, 22, 33]));
+        let backward = slice.iter().rev().map(|v| v.value());
+        assert!(backward.eq([33, 22, 11]));
+
+        let middle = slice.slice(1..2);
+        assert_eq!(middle.len(), 1);
+        assert_eq!(middle[0].value(), 22);
+        assert!(slice.get_slice(2..4).is_none());
+        assert!(slice.slice(3..).is_empty());
+    }
+
+    #[test]
+    fn empty_and_zero_sized() {
+        let empty: [(u8, u32); 0] = [];
+        let slice = DynSlice::<dyn Value>::new(&empty);
+        assert!(slice.is_empty());
+        assert_eq!(slice.metadata().layout(), Layout::new::<(u8, u32)>());
+        assert_eq!(slice.iter().count(), 0);
+
+        let units = [(); 5];
+        let slice = DynSlice::<dyn Value>::new(&units);
+        assert_eq!(slice.iter().map(|v| v.value()).sum::<u32>(), 35);
+    }
+}
@@ -0,0 +1,666 @@
+//! A contiguous growable vector of trait objects.
+
+use alloc::{
+    alloc::{alloc, dealloc, handle_alloc_error, realloc},
+    vec::Vec,
+};
+use core::{
+    alloc::Layout,
+    fmt,
+    iter::FusedIterator,
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+    ptr::{self, NonNull},
+    slice,
+};
+
+use crate::{from_raw_parts_mut, DynMetadata, Pointee, Unsize};
+
+// The smallest number of bytes allocated for a non-empty buffer.
+const MIN_CAPACITY: usize = 64;
+
+struct Entry<T: ?Sized> {
+    offset: usize,
+    metadata: DynMetadata<T>,
+}
+
+// Manual impl needed to avoid `T: Copy` bound.
+impl<T: ?Sized> Copy for Entry<T> {}
+
+// Manual impl needed to avoid `T: Clone` bound.
+impl<T: ?Sized> Clone for Entry<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Entry<T> {
+    #[inline]
+    fn end(self) -> usize {
+        self.offset + self.metadata.size_of()
+    }
+}
+
+/// A contiguous growable vector of trait objects.
+///
+/// Unlike `Vec<Box<dyn Trait>>`, a `DynVec<dyn Trait>` stores all of its
+/// elements in a single byte buffer. Each element is placed at an offset which
+/// satisfies its own alignment, and the element's [`DynMetadata`] is kept
+/// alongside that offset so the element can be accessed and dropped later.
+///
+/// # Example
+///
+/// ```
+/// use ptr_meta::DynVec;
+///
+/// #[ptr_meta::pointee]
+/// trait Shape {
+///     fn area(&self) -> f64;
+/// }
+///
+/// struct Square(f64);
+///
+/// impl Shape for Square {
+///     fn area(&self) -> f64 {
+///         self.0 * self.0
+///     }
+/// }
+///
+/// struct Circle(f64);
+///
+/// impl Shape for Circle {
+///     fn area(&self) -> f64 {
+///         3.0 * self.0 * self.0
+///     }
+/// }
+///
+/// let mut shapes = DynVec::<dyn Shape>::new();
+/// shapes.push(Square(2.0));
+/// shapes.push(Circle(1.0));
+///
+/// assert_eq!(shapes.len(), 2);
+/// assert_eq!(shapes[0].area(), 4.0);
+/// assert_eq!(shapes.iter().map(|s| s.area()).sum::<f64>(), 7.0);
+/// ```
+pub struct DynVec<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> {
+    ptr: NonNull<u8>,
+    cap: usize,
+    align: usize,
+    // The end of the highest element in the buffer. Every live element lies
+    // entirely before this offset.
+    len: usize,
+    entries: Vec<Entry<T>>,
+    _phantom: PhantomData<T>,
+}
+
+// SAFETY: `DynVec` owns its elements, so it can be sent to another thread
+// whenever its elements can be.
+unsafe impl<T> Send for DynVec<T> where
+    T: Pointee<Metadata = DynMetadata<T>> + Send + ?Sized
+{
+}
+
+// SAFETY: `DynVec` only hands out shared references to its elements from a
+// shared reference to itself.
+unsafe impl<T> Sync for DynVec<T> where
+    T: Pointee<Metadata = DynMetadata<T>> + Sync + ?Sized
+{
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> DynVec<T> {
+    /// Returns a new, empty `DynVec`.
+    ///
+    /// This does not allocate until the first element is pushed.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            cap: 0,
+            align: 1,
+            len: 0,
+            entries: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the number of elements in the vector.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the vector contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of bytes the element buffer can hold without
+    /// reallocating.
+    #[inline]
+    pub fn byte_capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Moves `value` to the end of the vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer would grow past `isize::MAX` bytes.
+    pub fn push<U: Unsize<T>>(&mut self, value: U) {
+        let metadata = U::unsize_metadata();
+        let layout = Layout::new::<U>();
+
+        self.entries.reserve(1);
+        let offset = self.reserve_for(layout);
+        // SAFETY: `reserve_for` returned an offset into the buffer which is
+        // suitably aligned for `U` and has room for `size_of::<U>()` bytes.
+        unsafe {
+            self.ptr.as_ptr().add(offset).cast::<U>().write(value);
+        }
+        self.entries.push(Entry { offset, metadata });
+    }
+
+    /// Returns a reference to the element at `index`, or `None` if it is out
+    /// of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let entry = *self.entries.get(index)?;
+        // SAFETY: `entry` describes a live, initialized element of the vector.
+        Some(unsafe { &*self.element_ptr(entry) })
+    }
+
+    /// Returns a mutable reference to the element at `index`, or `None` if it
+    /// is out of bounds.
+    #[inline]
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let entry = *self.entries.get(index)?;
+        // SAFETY: `entry` describes a live, initialized element of the vector,
+        // and we have exclusive access to the vector.
+        Some(unsafe { &mut *self.element_ptr(entry) })
+    }
+
+    /// Returns an iterator over the elements of the vector.
+    #[inline]
+    pub fn iter(&self) -> DynVecIter<'_, T> {
+        DynVecIter {
+            ptr: self.ptr,
+            entries: self.entries.iter(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over mutable references to the elements of the
+    /// vector.
+    #[inline]
+    pub fn iter_mut(&mut self) -> DynVecIterMut<'_, T> {
+        DynVecIterMut {
+            ptr: self.ptr,
+            entries: self.entries.iter(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Removes and drops the element at `index`.
+    ///
+    /// The last element of the vector takes its place, so this does not
+    /// preserve ordering. When it fits, the last element is also moved into
+    /// the bytes freed by the removed element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn swap_remove(&mut self, index: usize) {
+        let len = self.entries.len();
+        assert!(
+            index < len,
+            "swap_remove index (is {index}) should be < len (is {len})",
+        );
+
+        let removed = self.entries.swap_remove(index);
+        // SAFETY: `removed` described a live element, and it has been removed
+        // from the entries so it will not be dropped again.
+        unsafe {
+            ptr::drop_in_place(self.element_ptr(removed));
+        }
+
+        if self.entries.is_empty() {
+            self.len = 0;
+        } else if removed.end() == self.len {
+            self.len = removed.offset;
+        } else if index < self.entries.len() {
+            let moved = &mut self.entries[index];
+            if moved.end() == self.len {
+                let layout = moved.metadata.layout();
+                let target = removed.offset.next_multiple_of(layout.align());
+                if target + layout.size() <= removed.end() {
+                    // SAFETY: Both ranges lie inside of the buffer. The source
+                    // holds the moved element and the target lies within the
+                    // bytes of the removed element, so they do not overlap.
+                    unsafe {
+                        ptr::copy_nonoverlapping(
+                            self.ptr.as_ptr().add(moved.offset),
+                            self.ptr.as_ptr().add(target),
+                            layout.size(),
+                        );
+                    }
+                    self.len = moved.offset;
+                    moved.offset = target;
+                }
+            }
+        }
+    }
+
+    /// Drops all of the elements in the vector.
+    ///
+    /// This does not release the vector's buffer.
+    pub fn clear(&mut self) {
+        let entries = core::mem::take(&mut self.entries);
+        self.len = 0;
+        for entry in entries.iter() {
+            // SAFETY: Each entry describes a live element, and the entries have
+            // been removed from the vector so they will not be dropped again.
+            unsafe {
+                ptr::drop_in_place(self.element_ptr(*entry));
+            }
+        }
+        self.entries = entries;
+        self.entries.clear();
+    }
+
+    #[inline]
+    fn element_ptr(&self, entry: Entry<T>) -> *mut T {
+        from_raw_parts_mut(
+            self.ptr.as_ptr().wrapping_add(entry.offset).cast(),
+            entry.metadata,
+        )
+    }
+
+    #[inline]
+    fn buffer_layout(&self) -> Layout {
+        // SAFETY: This layout was checked when the buffer was allocated.
+        unsafe { Layout::from_size_align_unchecked(self.cap, self.align) }
+    }
+
+    // Reserves space for a value with the given layout at the end of the
+    // buffer and returns its offset.
+    fn reserve_for(&mut self, layout: Layout) -> usize {
+        let offset = self
+            .len
+            .checked_next_multiple_of(layout.align())
+            .expect("capacity overflow");
+        let end = offset
+            .checked_add(layout.size())
+            .expect("capacity overflow");
+        let align = self.align.max(layout.align());
+
+        if end > self.cap || align > self.align {
+            self.grow(end, align);
+        }
+        self.len = end;
+
+        offset
+    }
+
+    fn grow(&mut self, min_cap: usize, align: usize) {
+        let cap = min_cap.max(self.cap.saturating_mul(2)).max(MIN_CAPACITY);
+        let layout =
+            Layout::from_size_align(cap, align).expect("capacity overflow");
+
+        let ptr = if self.cap == 0 {
+            // SAFETY: `layout` has a non-zero size.
+            unsafe { alloc(layout) }
+        } else if align == self.align {
+            // SAFETY: The buffer was allocated with `buffer_layout`, and `cap`
+            // does not overflow `isize` when rounded up to `align`.
+            unsafe { realloc(self.ptr.as_ptr(), self.buffer_layout(), cap) }
+        } else {
+            // SAFETY: `layout` has a non-zero size.
+            let new_ptr = unsafe { alloc(layout) };
+            if !new_ptr.is_null() {
+                // SAFETY: The first `len` bytes of the old buffer are readable
+                // and the new buffer is at least `len` bytes long. The old
+                // buffer was allocated with `buffer_layout`.
+                unsafe {
+                    ptr::copy_nonoverlapping(
+                        self.ptr.as_ptr(),
+                        new_ptr,
+                        self.len,
+                    );
+                    dealloc(self.ptr.as_ptr(), self.buffer_layout());
+                }
+            }
+            new_ptr
+        };
+
+        self.ptr =
+            NonNull::new(ptr).unwrap_or_else(|| handle_alloc_error(layout));
+        self.cap = cap;
+        self.align = align;
+    }
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> Drop for DynVec<T> {
+    fn drop(&mut self) {
+        self.clear();
+        if self.cap != 0 {
+            // SAFETY: The buffer was allocated with `buffer_layout`.
+            unsafe {
+                dealloc(self.ptr.as_ptr(), self.buffer_layout());
+            }
+        }
+    }
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> Default for DynVec<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for DynVec<T>
+where
+    T: Pointee<Metadata = DynMetadata<T>> + fmt::Debug + ?Sized,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> Index<usize>
+    for DynVec<T>
+{
+    type Output = T;
+
+    #[inline]
+    fn index(&self, index: usize) -> &T {
+        let len = self.len();
+        self.get(index).unwrap_or_else(|| {
+            panic!(
+                "index out of bounds: the len is {len} but the index is \
+                 {index}"
+            )
+        })
+    }
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> IndexMut<usize>
+    for DynVec<T>
+{
+    #[inline]
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        let len = self.len();
+        self.get_mut(index).unwrap_or_else(|| {
+            panic!(
+                "index out of bounds: the len is {len} but the index is \
+                 {index}"
+            )
+        })
+    }
+}
+
+impl<'a, T: Pointee<Metadata = DynMetadata<T>> + ?Sized> IntoIterator
+    for &'a DynVec<T>
+{
+    type Item = &'a T;
+    type IntoIter = DynVecIter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T: Pointee<Metadata = DynMetadata<T>> + ?Sized> IntoIterator
+    for &'a mut DynVec<T>
+{
+    type Item = &'a mut T;
+    type IntoIter = DynVecIterMut<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// An iterator over the elements of a [`DynVec`].
+pub struct DynVecIter<'a, T: Pointee<Metadata = DynMetadata<T>> + ?Sized> {
+    ptr: NonNull<u8>,
+    entries: slice::Iter<'a, Entry<T>>,
+    _phantom: PhantomData<&'a T>,
+}
+
+impl<'a, T: Pointee<Metadata = DynMetadata<T>> + ?Sized> DynVecIter<'a, T> {
+    #[inline]
+    fn get(&self, entry: Entry<T>) -> &'a T {
+        // SAFETY: `entry` describes a live element of the vector this iterator
+        // borrows from.
+        unsafe {
+            &*from_raw_parts_mut::<T>(
+                self.ptr.as_ptr().add(entry.offset).cast(),
+                entry.metadata,
+            )
+        }
+    }
+}
+
+impl<'a, T: Pointee<Metadata = DynMetadata<T>> + ?Sized> Iterator
+    for DynVecIter<'a, T>
+{
+    type Item = &'a T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = *self.entries.next()?;
+        Some(self.get(entry))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> DoubleEndedIterator
+    for DynVecIter<'_, T>
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let entry = *self.entries.next_back()?;
+        Some(self.get(entry))
+    }
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> ExactSizeIterator
+    for DynVecIter<'_, T>
+{
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> FusedIterator
+    for DynVecIter<'_, T>
+{
+}
+
+/// An iterator over mutable references to the elements of a [`DynVec`].
+pub struct DynVecIterMut<'a, T: Pointee<Metadata = DynMetadata<T>> + ?Sized> {
+    ptr: NonNull<u8>,
+    entries: slice::Iter<'a, Entry<T>>,
+    _phantom: PhantomData<&'a mut T>,
+}
+
+impl<'a, T: Pointee<Metadata = DynMetadata<T>> + ?Sized> DynVecIterMut<'a, T> {
+    #[inline]
+    fn get(&self, entry: Entry<T>) -> &'a mut T {
+        // SAFETY: `entry` describes a live element of the vector this iterator
+        // exclusively borrows from. Each entry is yielded at most once and
+        // entries never overlap, so the returned references are disjoint.
+        unsafe {
+            &mut *from_raw_parts_mut::<T>(
+                self.ptr.as_ptr().add(entry.offset).cast(),
+                entry.metadata,
+            )
+        }
+    }
+}
+
+impl<'a, T: Pointee<Metadata = DynMetadata<T>> + ?Sized> Iterator
+    for DynVecIterMut<'a, T>
+{
+    type Item = &'a mut T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = *self.entries.next()?;
+        Some(self.get(entry))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> DoubleEndedIterator
+    for DynVecIterMut<'_, T>
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let entry = *self.entries.next_back()?;
+        Some(self.get(entry))
+    }
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> ExactSizeIterator
+    for DynVecIterMut<'_, T>
+{
+}
+
+impl<T: Pointee<Metadata = DynMetadata<T>> + ?Sized> FusedIterator
+    for DynVecIterMut<'_, T>
+{
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+
+    use super::DynVec;
+
+    #[crate::pointee(crate)]
+    trait Value {
+        fn value(&self) -> u64;
+        fn set(&mut self, value: u64);
+        fn address(&self) -> usize;
+    }
+
+    #[repr(align(32))]
+    struct Aligned(u64);
+
+    impl Value for Aligned {
+        fn value(&self) -> u64 {
+            self.0
+        }
+        fn set(&mut self, value: u64) {
+            self.0 = value;
+        }
+        fn address(&self) -> usize {
+            self as *const Self as usize
+        }
+    }
+
+    impl Value for u8 {
+        fn value(&self) -> u64 {
+            *self as u64
+        }
+        fn set(&mut self, value: u64) {
+            *self = value as u8;
+        }
+        fn address(&self) -> usize {
+            self as *const Self as usize
+        }
+    }
+
+    struct Unit;
+
+    impl Value for Unit {
+        fn value(&self) -> u64 {
+            0
+        }
+        fn set(&mut self, _: u64) {}
+        fn address(&self) -> usize {
+            self as *const Self as usize
+        }
+    }
+
+    struct Counted(u64, Rc<Cell<usize>>);
+
+    impl Value for Counted {
+        fn value(&self) -> u64 {
+            self.0
+        }
+        fn set(&mut self, _: u64) {}
+        fn address(&self) -> usize {
+            self as *const Self as usize
+        }
+    }
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.1.set(self.1.get() + 1);
+        }
+    }
+
+    #[test]
+    fn push_and_index() {
+        let mut vec = DynVec::<dyn Value>::new();
+        for i in 0..100u64 {
+            match i % 3 {
+                0 => vec.push(i as u8),
+                1 => vec.push(Aligned(i)),
+                _ => vec.push(Unit),
+            }
+        }
+
+        assert_eq!(vec.len(), 100);
+        for (i, value) in vec.iter().enumerate() {
+            let expected = if i % 3 == 2 { 0 } else { i as u64 };
+            assert_eq!(value.value(), expected);
+        }
+        for i in (1..100).step_by(3) {
+            assert_eq!(vec[i].address() % 32, 0);
+        }
+
+        for value in vec.iter_mut() {
+            value.set(7);
+        }
+        assert_eq!(vec[0].value(), 7);
+        assert_eq!(vec[1].value(), 7);
+        assert!(vec.get(100).is_none());
+    }
+
+    #[test]
+    fn swap_remove_drops() {
+        let drops = Rc::new(Cell::new(0));
+        let mut vec = DynVec::<dyn Value>::new();
+        vec.push(1u8);
+        vec.push(Counted(2, drops.clone()));
+        vec.push(Aligned(3));
+        vec.push(Counted(4, drops.clone()));
+
+        vec.swap_remove(1);
+        assert_eq!(drops.get(), 1);
+        assert_eq!(vec.len(), 3);
+        assert_eq!(vec[1].value(), 4);
+        assert_eq!(vec[2].value(), 3);
+
+        vec.swap_remove(0);
+        assert_eq!(vec[0].value(), 3);
+        assert_eq!(vec[1].value(), 4);
+        assert_eq!(vec[0].address() % 32, 0);
+
+        drop(vec);
+        assert_eq!(drops.get(), 2);
+    }
+}
@@ -0,0 +1,11 @@
+//! Helpers for derived code which accesses the fields of DSTs.
+
+/// Returns the offset of `field` from the start of `base` in bytes.
+#[doc(hidden)]
+#[inline]
+pub fn field_offset<T: ?Sized, F: ?Sized>(
+    base: *const T,
+    field: *const F,
+) -> usize {
+    field as *const u8 as usize - base as *const u8 as usize
+}
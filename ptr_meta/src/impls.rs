@@ -1,6 +1,6 @@
-use core::{any::Any, error::Error};
+use core::{alloc::Layout, any::Any, error::Error};
 
-use crate::{DynMetadata, Pointee};
+use crate::{DynMetadata, LayoutFromMetadata, Pointee};
 
 // SAFETY: The metadata type of `dyn Any` is `DynMetadata<dyn Any>`.
 unsafe impl Pointee for dyn Any {
@@ -47,3 +47,31 @@ unsafe impl Pointee for dyn Error + Sync {
 unsafe impl Pointee for dyn Error + Send + Sync {
     type Metadata = DynMetadata<dyn Error + Send + Sync>;
 }
+
+macro_rules! impl_layout_from_metadata {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            // SAFETY: The vtable of a trait object records the layout of the
+            // underlying type.
+            unsafe impl LayoutFromMetadata for $ty {
+                #[inline]
+                fn layout_for_metadata(
+                    metadata: Self::Metadata,
+                ) -> Option<Layout> {
+                    Some(metadata.layout())
+                }
+            }
+        )*
+    };
+}
+
+impl_layout_from_metadata! {
+    dyn Any,
+    dyn Any + Send,
+    dyn Any + Sync,
+    dyn Any + Send + Sync,
+    dyn Error,
+    dyn Error + Send,
+    dyn Error + Sync,
+    dyn Error + Send + Sync,
+}
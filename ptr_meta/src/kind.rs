@@ -0,0 +1,178 @@
+use core::{any::Any, error::Error, ffi::CStr};
+
+use crate::{DynMetadata, Pointee};
+
+/// The kind of metadata that pointers to a type have.
+///
+/// Every [`Pointee`] with `()`, `usize`, or [`DynMetadata`] metadata has a
+/// [`KIND`](PointeeKind::KIND), which generic code can branch on at compile
+/// time.
+///
+/// # Example
+///
+/// ```
+/// use ptr_meta::{MetadataKind, PointeeKind};
+///
+/// fn describe<T: PointeeKind + ?Sized>() -> &'static str {
+///     match T::KIND {
+///         MetadataKind::Thin => "thin",
+///         MetadataKind::Slice => "slice",
+///         MetadataKind::Dyn => "trait object",
+///     }
+/// }
+///
+/// assert_eq!(describe::<u32>(), "thin");
+/// assert_eq!(describe::<str>(), "slice");
+/// assert_eq!(describe::<dyn core::any::Any>(), "trait object");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MetadataKind {
+    /// The metadata is `()`.
+    Thin,
+    /// The metadata is a `usize` length.
+    Slice,
+    /// The metadata is a [`DynMetadata`].
+    Dyn,
+}
+
+/// A type which is the metadata of some [`Pointee`].
+///
+/// This is implemented for `()`, `usize`, and [`DynMetadata`].
+pub trait PointerMetadata {
+    /// The kind of metadata this type is.
+    const KIND: MetadataKind;
+}
+
+impl PointerMetadata for () {
+    const KIND: MetadataKind = MetadataKind::Thin;
+}
+
+impl PointerMetadata for usize {
+    const KIND: MetadataKind = MetadataKind::Slice;
+}
+
+impl<Dyn: ?Sized> PointerMetadata for DynMetadata<Dyn> {
+    const KIND: MetadataKind = MetadataKind::Dyn;
+}
+
+/// A [`Pointee`] whose kind of metadata is known.
+///
+/// This is implemented for every `Pointee` whose metadata implements
+/// [`PointerMetadata`].
+pub trait PointeeKind: Pointee {
+    /// The kind of metadata pointers to this type have.
+    const KIND: MetadataKind;
+}
+
+impl<T> PointeeKind for T
+where
+    T: Pointee + ?Sized,
+    T::Metadata: PointerMetadata,
+{
+    const KIND: MetadataKind = <T::Metadata as PointerMetadata>::KIND;
+}
+
+/// A type which pointers to are thin.
+///
+/// This is implemented for every `Sized` type.
+pub trait ThinPointee: Pointee<Metadata = ()> {}
+
+impl<T> ThinPointee for T {}
+
+/// A type which is a sequence of elements, optionally preceded by a header.
+///
+/// The metadata of a `SliceLikePointee` is the number of `Element`s it
+/// contains. This is implemented for slices, `str`, `CStr`, and `OsStr`.
+/// `#[derive(Pointee)]` implements it for structs whose last field is a slice
+/// or `str`.
+pub trait SliceLikePointee: Pointee<Metadata = usize> {
+    /// The type of the elements in the sequence.
+    type Element;
+}
+
+impl<T> SliceLikePointee for [T] {
+    type Element = T;
+}
+
+impl SliceLikePointee for str {
+    type Element = u8;
+}
+
+impl SliceLikePointee for CStr {
+    type Element = u8;
+}
+
+#[cfg(feature = "std")]
+impl SliceLikePointee for std::ffi::OsStr {
+    type Element = u8;
+}
+
+/// A trait object type.
+///
+/// This is implemented for `dyn Any` and `dyn Error` (optionally with `+ Send`
+/// and/or `+ Sync`). `#[ptr_meta::pointee]` implements it for the trait
+/// objects it generates `Pointee` implementations for.
+///
+/// Structs with a trailing trait object are not `DynPointee` because their
+/// metadata is the metadata of the trait object, not their own.
+pub trait DynPointee: Pointee<Metadata = DynMetadata<Self>> {}
+
+impl DynPointee for dyn Any {}
+impl DynPointee for dyn Any + Send {}
+impl DynPointee for dyn Any + Sync {}
+impl DynPointee for dyn Any + Send + Sync {}
+impl DynPointee for dyn Error {}
+impl DynPointee for dyn Error + Send {}
+impl DynPointee for dyn Error + Sync {}
+impl DynPointee for dyn Error + Send + Sync {}
+
+#[cfg(test)]
+mod tests {
+    use core::any::Any;
+
+    use super::{
+        DynPointee, MetadataKind, PointeeKind, SliceLikePointee, ThinPointee,
+    };
+
+    fn element_size<T: SliceLikePointee + ?Sized>() -> usize {
+        core::mem::size_of::<T::Element>()
+    }
+
+    fn is_thin<T: ThinPointee + ?Sized>() -> bool {
+        T::KIND == MetadataKind::Thin
+    }
+
+    fn is_dyn<T: DynPointee + ?Sized>() -> bool {
+        T::KIND == MetadataKind::Dyn
+    }
+
+    #[test]
+    fn kinds() {
+        assert!(is_thin::<u64>());
+        assert!(is_thin::<[u8; 4]>());
+        assert_eq!(<[u16]>::KIND, MetadataKind::Slice);
+        assert_eq!(<core::ffi::CStr as PointeeKind>::KIND, MetadataKind::Slice);
+        assert_eq!(element_size::<[u16]>(), 2);
+        assert_eq!(element_size::<str>(), 1);
+        assert!(is_dyn::<dyn Any + Send>());
+        assert!(is_dyn::<dyn core::error::Error>());
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derived() {
+        #[derive(crate::Pointee)]
+        #[ptr_meta(crate)]
+        struct Packet {
+            _id: u32,
+            _payload: [u32],
+        }
+
+        #[crate::pointee(crate)]
+        trait Handler {}
+
+        assert_eq!(Packet::KIND, MetadataKind::Slice);
+        assert_eq!(element_size::<Packet>(), 4);
+        assert!(is_dyn::<dyn Handler>());
+    }
+}
@@ -0,0 +1,403 @@
+#[cfg(feature = "alloc")]
+use alloc::{
+    alloc::{alloc, alloc_zeroed, dealloc, handle_alloc_error},
+    boxed::Box,
+};
+use core::{alloc::Layout, ffi::CStr, mem::align_of};
+#[cfg(feature = "alloc")]
+use core::{
+    fmt,
+    mem::ManuallyDrop,
+    ptr::{self, NonNull},
+};
+
+use crate::Pointee;
+#[cfg(feature = "alloc")]
+use crate::{from_raw_parts_mut, metadata};
+
+/// A pointee whose layout can be computed from its pointer metadata alone.
+///
+/// This is implemented for all `Sized` types, slices, `str`, `CStr`, `OsStr`,
+/// and the provided trait objects. `#[ptr_meta::pointee]` implements it for
+/// trait objects, and `#[derive(Pointee)]` implements it for `#[repr(C)]` and
+/// `#[repr(transparent)]` structs whose last field implements it.
+///
+/// # Safety
+///
+/// - `layout_for_metadata` must return the layout of a value of `Self` with the
+///   given pointer metadata, or `None` if that layout would overflow `isize`.
+/// - `MIN_ALIGN` must be a power of two which is less than or equal to the
+///   alignment of `Self` for every possible metadata.
+pub unsafe trait LayoutFromMetadata: Pointee {
+    /// A lower bound on the alignment of `Self` which is known statically.
+    ///
+    /// This is the exact alignment for `Sized` types and slices. For trait
+    /// objects, the alignment depends on the underlying type and so this
+    /// defaults to `1`.
+    const MIN_ALIGN: usize = 1;
+
+    /// Returns the layout of a value of `Self` with the given metadata.
+    fn layout_for_metadata(metadata: Self::Metadata) -> Option<Layout>;
+}
+
+// SAFETY: The layout of a `Sized` type does not depend on its metadata.
+unsafe impl<T> LayoutFromMetadata for T {
+    const MIN_ALIGN: usize = align_of::<T>();
+
+    #[inline]
+    fn layout_for_metadata(_: ()) -> Option<Layout> {
+        Some(Layout::new::<T>())
+    }
+}
+
+// SAFETY: A slice is laid out as an array of its elements.
+unsafe impl<T> LayoutFromMetadata for [T] {
+    const MIN_ALIGN: usize = align_of::<T>();
+
+    #[inline]
+    fn layout_for_metadata(len: usize) -> Option<Layout> {
+        Layout::array::<T>(len).ok()
+    }
+}
+
+// SAFETY: A `str` is laid out as an array of bytes.
+unsafe impl LayoutFromMetadata for str {
+    #[inline]
+    fn layout_for_metadata(len: usize) -> Option<Layout> {
+        Layout::array::<u8>(len).ok()
+    }
+}
+
+// SAFETY: A `CStr` is laid out as an array of bytes.
+unsafe impl LayoutFromMetadata for CStr {
+    #[inline]
+    fn layout_for_metadata(len: usize) -> Option<Layout> {
+        Layout::array::<u8>(len).ok()
+    }
+}
+
+#[cfg(feature = "std")]
+// SAFETY: An `OsStr` is laid out as an array of bytes.
+unsafe impl LayoutFromMetadata for std::ffi::OsStr {
+    #[inline]
+    fn layout_for_metadata(len: usize) -> Option<Layout> {
+        Layout::array::<u8>(len).ok()
+    }
+}
+
+/// An owned allocation for a possibly-uninitialized value of an unsized type.
+///
+/// `MaybeUninitDst<T>` owns memory with the layout of a `T` with some pointer
+/// metadata. It is the unsized counterpart to `Box<MaybeUninit<T>>`, and is
+/// usually created with [`new_uninit_with_metadata`] or
+/// [`new_zeroed_with_metadata`].
+///
+/// The contents may be uninitialized, so they are never treated as a `T`
+/// until [`assume_init`](Self::assume_init) is called. Until then, they can
+/// only be accessed through the raw pointers returned by
+/// [`as_ptr`](Self::as_ptr), [`as_mut_ptr`](Self::as_mut_ptr), and
+/// [`as_non_null`](Self::as_non_null). Dropping a `MaybeUninitDst<T>` frees
+/// its memory without dropping its contents.
+#[cfg(feature = "alloc")]
+pub struct MaybeUninitDst<T: LayoutFromMetadata + ?Sized> {
+    ptr: NonNull<T>,
+}
+
+// SAFETY: `MaybeUninitDst<T>` owns its contents like a `Box<T>` would.
+#[cfg(feature = "alloc")]
+unsafe impl<T: LayoutFromMetadata + Send + ?Sized> Send for MaybeUninitDst<T> {}
+
+// SAFETY: `MaybeUninitDst<T>` owns its contents like a `Box<T>` would.
+#[cfg(feature = "alloc")]
+unsafe impl<T: LayoutFromMetadata + Sync + ?Sized> Sync for MaybeUninitDst<T> {}
+
+#[cfg(feature = "alloc")]
+impl<T: LayoutFromMetadata + ?Sized> MaybeUninitDst<T> {
+    /// Returns a pointer to the contained value.
+    #[inline]
+    pub fn as_ptr(&self) -> *const T {
+        self.ptr.as_ptr()
+    }
+
+    /// Returns a mutable pointer to the contained value.
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    /// Returns a non-null pointer to the contained value.
+    #[inline]
+    pub fn as_non_null(&mut self) -> NonNull<T> {
+        self.ptr
+    }
+
+    /// Returns the pointer metadata of the contained value.
+    #[inline]
+    pub fn metadata(&self) -> T::Metadata {
+        metadata(self.as_ptr())
+    }
+
+    /// Converts a `MaybeUninitDst<T>` to a `Box<T>`.
+    ///
+    /// # Safety
+    ///
+    /// The contained value must be fully initialized.
+    #[inline]
+    pub unsafe fn assume_init(this: Self) -> Box<T> {
+        let this = ManuallyDrop::new(this);
+        // SAFETY: `this.ptr` was allocated by the global allocator with the
+        // layout of `T`, or is dangling and suitably aligned if `T` is
+        // zero-sized. The caller has guaranteed that the value is
+        // initialized, and `this` is not dropped so the allocation is not
+        // freed.
+        unsafe { Box::from_raw(this.ptr.as_ptr()) }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: LayoutFromMetadata + ?Sized> Drop for MaybeUninitDst<T> {
+    fn drop(&mut self) {
+        // SAFETY: `self.ptr` was returned by `alloc_dst` or `alloc_zeroed_dst`
+        // and has not been freed.
+        unsafe {
+            dealloc_dst(self.ptr.as_ptr());
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: LayoutFromMetadata + ?Sized> fmt::Debug for MaybeUninitDst<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("MaybeUninitDst<..>")
+    }
+}
+
+/// Allocates an uninitialized value of `T` with the given metadata.
+///
+/// # Panics
+///
+/// Panics if the layout of the value would overflow `isize`.
+///
+/// # Example
+///
+/// ```
+/// use ptr_meta::{new_uninit_with_metadata, MaybeUninitDst};
+///
+/// let mut uninit = new_uninit_with_metadata::<[u32]>(3);
+/// let ptr = uninit.as_mut_ptr() as *mut u32;
+/// for i in 0..3 {
+///     unsafe { ptr.add(i).write(i as u32) };
+/// }
+/// let slice = unsafe { MaybeUninitDst::assume_init(uninit) };
+/// assert_eq!(*slice, [0, 1, 2]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn new_uninit_with_metadata<T: LayoutFromMetadata + ?Sized>(
+    metadata: T::Metadata,
+) -> MaybeUninitDst<T> {
+    let ptr = alloc_dst::<T>(metadata);
+    MaybeUninitDst {
+        // SAFETY: `alloc_dst` never returns a null pointer.
+        ptr: unsafe { NonNull::new_unchecked(ptr) },
+    }
+}
+
+/// Allocates a value of `T` with the given metadata whose bytes are all zero.
+///
+/// # Panics
+///
+/// Panics if the layout of the value would overflow `isize`.
+#[cfg(feature = "alloc")]
+pub fn new_zeroed_with_metadata<T: LayoutFromMetadata + ?Sized>(
+    metadata: T::Metadata,
+) -> MaybeUninitDst<T> {
+    let ptr = alloc_zeroed_dst::<T>(metadata);
+    MaybeUninitDst {
+        // SAFETY: `alloc_zeroed_dst` never returns a null pointer.
+        ptr: unsafe { NonNull::new_unchecked(ptr) },
+    }
+}
+
+/// Allocates memory for a value of `T` with the given metadata and returns a
+/// pointer to it.
+///
+/// The returned memory is uninitialized. It must be freed with
+/// [`dealloc_dst`]. If the value is zero-sized, no memory is allocated and the
+/// returned pointer is dangling but suitably aligned.
+///
+/// # Panics
+///
+/// Panics if the layout of the value would overflow `isize`.
+#[cfg(feature = "alloc")]
+pub fn alloc_dst<T: LayoutFromMetadata + ?Sized>(
+    metadata: T::Metadata,
+) -> *mut T {
+    // SAFETY: `alloc` may be called with any non-zero-sized layout.
+    unsafe { alloc_dst_with(metadata, alloc) }
+}
+
+/// Allocates zeroed memory for a value of `T` with the given metadata and
+/// returns a pointer to it.
+///
+/// See [`alloc_dst`] for more details.
+///
+/// # Panics
+///
+/// Panics if the layout of the value would overflow `isize`.
+#[cfg(feature = "alloc")]
+pub fn alloc_zeroed_dst<T: LayoutFromMetadata + ?Sized>(
+    metadata: T::Metadata,
+) -> *mut T {
+    // SAFETY: `alloc_zeroed` may be called with any non-zero-sized layout.
+    unsafe { alloc_dst_with(metadata, alloc_zeroed) }
+}
+
+// SAFETY: `allocate` must be safe to call with any non-zero-sized layout.
+#[cfg(feature = "alloc")]
+unsafe fn alloc_dst_with<T: LayoutFromMetadata + ?Sized>(
+    metadata: T::Metadata,
+    allocate: unsafe fn(Layout) -> *mut u8,
+) -> *mut T {
+    let layout = T::layout_for_metadata(metadata)
+        .expect("DST layout overflowed `isize`");
+    let data_address = if layout.size() == 0 {
+        ptr::null_mut::<u8>().wrapping_add(layout.align())
+    } else {
+        // SAFETY: `layout` has a non-zero size.
+        let ptr = unsafe { allocate(layout) };
+        if ptr.is_null() {
+            handle_alloc_error(layout);
+        }
+        ptr
+    };
+    from_raw_parts_mut(data_address.cast(), metadata)
+}
+
+/// Frees memory allocated by [`alloc_dst`] or [`alloc_zeroed_dst`].
+///
+/// This does not drop the value pointed to by `ptr`.
+///
+/// # Safety
+///
+/// `ptr` must have been returned by [`alloc_dst`] or [`alloc_zeroed_dst`] with
+/// the same metadata, and must not have been freed already.
+#[cfg(feature = "alloc")]
+pub unsafe fn dealloc_dst<T: LayoutFromMetadata + ?Sized>(ptr: *mut T) {
+    let layout = T::layout_for_metadata(metadata(ptr))
+        .expect("DST layout overflowed `isize`");
+    if layout.size() != 0 {
+        // SAFETY: The caller has guaranteed that `ptr` was allocated by the
+        // global allocator with this layout.
+        unsafe {
+            dealloc(ptr.cast(), layout);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use alloc::boxed::Box;
+    use core::{alloc::Layout, any::Any, mem::size_of};
+
+    use super::{
+        alloc_dst, dealloc_dst, new_uninit_with_metadata,
+        new_zeroed_with_metadata, LayoutFromMetadata, MaybeUninitDst,
+    };
+    use crate::metadata;
+
+    #[test]
+    fn builtin_layouts() {
+        assert_eq!(
+            <[u64]>::layout_for_metadata(3),
+            Some(Layout::new::<[u64; 3]>()),
+        );
+        assert_eq!(str::layout_for_metadata(5), Some(Layout::new::<[u8; 5]>()));
+        assert_eq!(<[u64]>::layout_for_metadata(usize::MAX), None);
+        assert_eq!(
+            <(u8, u32)>::layout_for_metadata(()),
+            Some(Layout::new::<(u8, u32)>()),
+        );
+
+        let vtable = metadata(&0u16 as &dyn Any);
+        assert_eq!(
+            <dyn Any>::layout_for_metadata(vtable),
+            Some(Layout::new::<u16>()),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derived_layouts() {
+        #[derive(crate::Pointee)]
+        #[ptr_meta(crate)]
+        #[repr(C)]
+        struct Block {
+            a: u8,
+            tail: [u32],
+        }
+
+        #[derive(crate::Pointee)]
+        #[ptr_meta(crate)]
+        #[repr(C)]
+        struct Erased {
+            a: u8,
+            tail: dyn Any,
+        }
+
+        let block = Block::layout_for_metadata(3).unwrap();
+        assert_eq!((block.size(), block.align()), (16, 4));
+
+        let vtable = metadata(&0u64 as &dyn Any);
+        let erased = Erased::layout_for_metadata(vtable).unwrap();
+        assert_eq!((erased.size(), erased.align()), (16, 8));
+    }
+
+    #[test]
+    fn min_align() {
+        assert_eq!(<u64 as LayoutFromMetadata>::MIN_ALIGN, 8);
+        assert_eq!(<[u32] as LayoutFromMetadata>::MIN_ALIGN, 4);
+        assert_eq!(<str as LayoutFromMetadata>::MIN_ALIGN, 1);
+        assert_eq!(<dyn Any as LayoutFromMetadata>::MIN_ALIGN, 1);
+    }
+
+    #[test]
+    fn zeroed_and_uninit() {
+        let zeroed = new_zeroed_with_metadata::<[u32]>(4);
+        assert_eq!(zeroed.metadata(), 4);
+        // SAFETY: All-zero bytes are a valid `[u32]`.
+        let zeroed = unsafe { MaybeUninitDst::assume_init(zeroed) };
+        assert_eq!(*zeroed, [0; 4]);
+
+        let vtable = metadata(&0u64 as &dyn Any);
+        let zeroed = new_zeroed_with_metadata::<dyn Any>(vtable);
+        // SAFETY: All-zero bytes are a valid `u64`.
+        let zeroed: Box<dyn Any> =
+            unsafe { MaybeUninitDst::assume_init(zeroed) };
+        assert_eq!(zeroed.downcast_ref::<u64>(), Some(&0));
+
+        let mut uninit = new_uninit_with_metadata::<str>(2);
+        // SAFETY: The value is two bytes long.
+        unsafe {
+            uninit.as_mut_ptr().cast::<[u8; 2]>().write(*b"hi");
+        }
+        // SAFETY: The value was initialized with valid UTF-8.
+        let s = unsafe { MaybeUninitDst::assume_init(uninit) };
+        assert_eq!(&*s, "hi");
+
+        let empty = new_uninit_with_metadata::<[u64]>(0);
+        assert_eq!(empty.as_ptr() as *const u64 as usize % size_of::<u64>(), 0);
+
+        // Dropping an uninitialized value frees it without dropping it.
+        drop(new_uninit_with_metadata::<dyn Any>(vtable));
+    }
+
+    #[test]
+    fn raw_alloc() {
+        let ptr = alloc_dst::<[u16]>(8);
+        assert_eq!(metadata(ptr), 8);
+        // SAFETY: `ptr` was returned by `alloc_dst`.
+        unsafe {
+            dealloc_dst(ptr);
+        }
+    }
+}
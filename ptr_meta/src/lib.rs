@@ -42,11 +42,39 @@
 //! }
 //! ```
 //!
+//! If such a struct is `#[repr(C)]` and its last field is a slice or `str`,
+//! the derive also implements [`SliceDst`] so it can be constructed with
+//! `DstBuilder`.
+//!
+//! Additional implementations can be requested with `#[ptr_meta(...)]`:
+//! [`Transparent`] for safe casts of transparent wrappers, [`ThinDst`] for
+//! structs which store their own length, and raw pointer projections for each
+//! field. See the derive documentation for details.
+//!
 //! Note that the last field is required to be a DST. Structs with a generic
 //! type as the last field may have conflicting blanket implementations, as the
-//! generic type may be `Sized`. A collection of specific implementations may be
-//! required in these cases, with the generic parameter set (for example) a
-//! slice, `str`, or specific trait object.
+//! generic type may be `Sized`. Instead, list the unsized types the last field
+//! may be with `tail_variants`, and an implementation will be generated for
+//! each of them:
+//!
+//! ```
+//! use core::any::Any;
+//!
+//! use ptr_meta::Pointee;
+//!
+//! #[derive(Pointee)]
+//! #[ptr_meta(tail_variants([u8], str, dyn Any))]
+//! struct Node<T: ?Sized> {
+//!     header: u32,
+//!     tail: T,
+//! }
+//!
+//! let node: &Node<dyn Any> = &Node {
+//!     header: 1,
+//!     tail: 2u16,
+//! };
+//! assert_eq!(node.tail.downcast_ref::<u16>(), Some(&2));
+//! ```
 //!
 //! ## Trait objects
 //!
@@ -62,13 +90,61 @@
 //! }
 //! ```
 //!
-//! Note that this will not produce implementations for `Trait + Send + Sync`.
+//! By default, this does not produce implementations for trait objects with
+//! auto traits like `dyn Stringy + Send`. Use
+//! `#[ptr_meta::pointee(auto_traits)]` to also produce them for `+ Send`, `+
+//! Sync`, and `+ Send + Sync`, or list the auto traits to combine with
+//! `auto_traits(Send, Sync, Unpin, ...)`.
+//!
+//! `#[ptr_meta::pointee(upcast(Debug, ...))]` also implements [`Upcast`] to
+//! convert trait objects into trait objects of the listed supertraits. On Rust
+//! 1.86 and later, it implements [`UpcastMetadata`] to convert their metadata
+//! as well.
+//!
+//! ## Metadata kinds
+//!
+//! Code which is generic over pointees can require a kind of metadata with
+//! [`ThinPointee`], [`SliceLikePointee`], and [`DynPointee`], or branch on
+//! [`PointeeKind::KIND`] at compile time. The derive and attribute macros
+//! implement these traits where they apply.
+//!
+//! ## Containers
+//!
+//! [`DynSlice`] views a slice of a single concrete type as a sequence of trait
+//! objects which share one vtable. [`TaggedPtr`] packs a small tag into the
+//! alignment bits of a possibly-wide pointer, and [`CompactPtr`] stores the
+//! length of a slice-like pointer in a `u32` or `u16`. [`RelPtr`] is a
+//! self-relative pointer for position-independent data like memory-mapped
+//! files. [`AtomicWidePtr`] atomically loads, stores, and exchanges wide
+//! pointers. [`DstReader`] decodes length-prefixed records of [`Plain`] DSTs
+//! from bytes. [`copy_unsized`] copies a DST into memory you manage.
+//! [`AnyPtr`] erases the pointee type of any pointer so it can be downcast
+//! later, and [`coerce_ptr`] unsizes pointers to types which implement
+//! [`Unsize`] on stable Rust.
+//!
+//! With the `alloc` feature enabled, `ptr_meta` also provides containers built
+//! on top of pointer metadata:
+//!
+//! - [`DynVec`] stores differently-sized trait objects in a single buffer.
+//! - [`DynArena`] bump-allocates values of any type, including trait objects
+//!   and slices, and drops them when it is reset.
+//! - `DstBuilder` constructs boxed [`SliceDst`]s from a header and an iterator
+//!   of elements.
+//! - `CloneUnsized` clones unsized values into new boxes.
+//! - `move_out_of_box` moves a boxed DST into a buffer without dropping it.
+//! - `DstWriter` encodes length-prefixed records for [`DstReader`].
+//! - `new_uninit_with_metadata` and `new_zeroed_with_metadata` allocate
+//!   uninitialized DSTs from their metadata for any type which implements
+//!   [`LayoutFromMetadata`].
 //!
 //! ## Features
 //!
 //! - `derive`: Re-exports the macros from `ptr_meta_derive`. Enabled by
 //!   default.
-//! - `std`: Enables additional impls for `std` types. Enabled by default.
+//! - `alloc`: Enables containers which require a global allocator. Enabled by
+//!   default.
+//! - `std`: Enables additional impls for `std` types. Implies `alloc`. Enabled
+//!   by default.
 //!
 //! ## Example
 #![doc = include_str!("../example.md")]
@@ -89,7 +165,35 @@
 #![cfg_attr(all(docsrs, not(doctest)), feature(doc_cfg, doc_auto_cfg))]
 #![cfg_attr(miri, allow(internal_features), feature(core_intrinsics))]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod any_ptr;
+#[cfg(target_has_atomic = "ptr")]
+mod atomic;
+#[cfg(feature = "alloc")]
+mod clone;
+mod compact;
+mod copy;
+#[cfg(feature = "alloc")]
+mod dyn_arena;
+mod dyn_slice;
+#[cfg(feature = "alloc")]
+mod dyn_vec;
+mod field;
 mod impls;
+mod kind;
+mod layout;
+mod plain;
+mod records;
+mod rel_ptr;
+mod slice_dst;
+mod tagged;
+mod thin_dst;
+mod transparent;
+mod unsize;
+mod upcast;
+mod validate;
 
 use core::{
     ffi::CStr,
@@ -100,6 +204,57 @@ use core::{
 #[cfg(feature = "derive")]
 pub use ptr_meta_derive::{pointee, Pointee};
 
+#[cfg(target_has_atomic = "ptr")]
+pub use self::atomic::AtomicWidePtr;
+pub use self::{
+    any_ptr::AnyPtr,
+    compact::{CompactLen, CompactPtr},
+    copy::copy_unsized,
+    dyn_slice::{DynSlice, DynSliceIter},
+    kind::{
+        DynPointee, MetadataKind, PointeeKind, PointerMetadata,
+        SliceLikePointee, ThinPointee,
+    },
+    layout::LayoutFromMetadata,
+    plain::Plain,
+    records::{DstReader, LengthPrefix, RecordError},
+    rel_ptr::{OffsetError, RelOffset, RelPtr},
+    slice_dst::{SliceDst, SliceTail},
+    tagged::TaggedPtr,
+    thin_dst::ThinDst,
+    transparent::Transparent,
+    unsize::{coerce_ptr, coerce_ptr_mut, Unsize, UnsizeFrom},
+    upcast::{Upcast, UpcastMetadata},
+    validate::{try_from_raw_parts, try_from_raw_parts_mut, ValidateMetadata},
+};
+#[cfg(feature = "alloc")]
+pub use self::{
+    clone::CloneUnsized,
+    copy::move_out_of_box,
+    dyn_arena::DynArena,
+    dyn_vec::{DynVec, DynVecIter, DynVecIterMut},
+    layout::{
+        alloc_dst, alloc_zeroed_dst, dealloc_dst, new_uninit_with_metadata,
+        new_zeroed_with_metadata, MaybeUninitDst,
+    },
+    records::DstWriter,
+    slice_dst::DstBuilder,
+};
+
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "alloc")]
+    pub use alloc::{borrow::ToOwned, boxed::Box};
+
+    #[cfg(feature = "alloc")]
+    pub use crate::clone::assemble_clone;
+    pub use crate::{
+        field::field_offset,
+        unsize::vtable_for,
+        upcast::{cast_dyn, dyn_metadata},
+    };
+}
+
 /// A trait which associates pointer metadata with a pointee type.
 ///
 /// # Pointer metadata
@@ -125,12 +280,23 @@ pub use ptr_meta_derive::{pointee, Pointee};
 /// In the future, Rust may add new kinds of types which have different pointer
 /// metadata.
 ///
+/// Generic code can require a particular kind of metadata with [`ThinPointee`],
+/// [`SliceLikePointee`], or [`DynPointee`], or branch on
+/// [`PointeeKind::KIND`].
+///
 /// [dst]: https://doc.rust-lang.org/reference/dynamically-sized-types.html
 ///
 /// # Safety
 ///
 /// The associated `Metadata` type must be the pointer metadata type for the
 /// implementing type.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not implement `Pointee`",
+    note = "trait objects implement `Pointee` when their trait has the \
+            `#[ptr_meta::pointee]` attribute",
+    note = "structs with a generic last field only implement `Pointee` for \
+            the types listed in `#[ptr_meta(tail_variants(...))]`"
+)]
 pub unsafe trait Pointee {
     /// The metadata type for pointers and references to this type.
     type Metadata: Copy + Send + Sync + Ord + Hash + Unpin;
@@ -495,7 +661,7 @@ mod tests {
 mod derive_tests {
     use core::any::Any;
 
-    use super::{test_pointee, Pointee};
+    use super::{from_raw_parts, from_raw_parts_mut, test_pointee, Pointee};
 
     #[test]
     fn trait_objects() {
@@ -556,6 +722,385 @@ mod derive_tests {
         }
     }
 
+    #[test]
+    fn tail_variants() {
+        #[crate::pointee(crate)]
+        trait TestTrait {
+            fn get(&self) -> u32;
+        }
+
+        impl TestTrait for u32 {
+            fn get(&self) -> u32 {
+                *self
+            }
+        }
+
+        #[derive(Pointee)]
+        #[ptr_meta(crate, tail_variants([u16], str, dyn Any, dyn TestTrait))]
+        #[repr(C)]
+        struct Node<H, T: ?Sized> {
+            header: H,
+            tail: T,
+        }
+
+        let slice: &Node<u8, [u16]> = &Node {
+            header: 1,
+            tail: [2, 3, 4],
+        };
+        test_pointee(slice);
+        assert_eq!(super::metadata(slice), 3);
+        assert_eq!(
+            <Node<u8, [u16]> as crate::SliceDst>::layout_for(3)
+                .unwrap()
+                .1,
+            2,
+        );
+
+        let bytes = [1u8, b'h', b'i'];
+        // SAFETY: `bytes` is a `u8` header followed by two bytes of UTF-8.
+        let text = unsafe {
+            &*from_raw_parts::<Node<u8, str>>(bytes.as_ptr().cast(), 2)
+        };
+        assert_eq!(text.header, 1);
+        assert_eq!(&text.tail, "hi");
+
+        let any: &Node<u8, dyn Any> = &Node {
+            header: 5,
+            tail: 6u64,
+        };
+        test_pointee(any);
+        assert_eq!(any.tail.downcast_ref::<u64>(), Some(&6));
+
+        let custom: &Node<(), dyn TestTrait> = &Node {
+            header: (),
+            tail: 7u32,
+        };
+        assert_eq!(custom.tail.get(), 7);
+        assert_eq!(
+            <Node<(), dyn TestTrait> as crate::PointeeKind>::KIND,
+            crate::MetadataKind::Dyn
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn tail_variants_without_impls() {
+        use crate::CloneUnsized;
+
+        // `dyn Any` doesn't implement `CloneUnsized`, so only `Node<[u16]>`
+        // does.
+        #[derive(Pointee)]
+        #[ptr_meta(crate, clone_unsized, tail_variants([u16], dyn Any))]
+        #[repr(C)]
+        struct Node<T: ?Sized> {
+            header: u8,
+            tail: T,
+        }
+
+        let slice: &Node<[u16]> = &Node {
+            header: 1,
+            tail: [2, 3],
+        };
+        let cloned = slice.clone_to_box();
+        assert_eq!(cloned.header, 1);
+        assert_eq!(cloned.tail, [2, 3]);
+
+        let any: &Node<dyn Any> = &Node {
+            header: 4,
+            tail: 5u8,
+        };
+        test_pointee(any);
+    }
+
+    #[test]
+    fn projections() {
+        use core::{mem::MaybeUninit, ptr::NonNull};
+
+        #[derive(Pointee)]
+        #[ptr_meta(crate, projections)]
+        #[repr(C)]
+        struct Block<H, T> {
+            header: H,
+            elements: [T],
+        }
+
+        let mut buffer = [MaybeUninit::<u32>::uninit(); 4];
+        let ptr = from_raw_parts_mut::<Block<u32, u16>>(
+            buffer.as_mut_ptr().cast(),
+            3,
+        );
+        // SAFETY: `buffer` is large enough and aligned for a `Block` with three
+        // elements.
+        unsafe {
+            Block::project_header_mut(ptr).write(7);
+            let elements = Block::project_tail_mut(ptr);
+            assert_eq!(elements.len(), 3);
+            for i in 0..3 {
+                elements.cast::<u16>().add(i).write(i as u16 * 10);
+            }
+        }
+
+        // SAFETY: Every field of the `Block` has been initialized.
+        let block = unsafe { &*ptr };
+        assert_eq!(block.header, 7);
+        assert_eq!(&block.elements, &[0, 10, 20]);
+
+        let non_null = NonNull::from(block);
+        // SAFETY: `non_null` points to `block`.
+        unsafe {
+            assert!(core::ptr::eq(
+                Block::project_header(non_null.as_ptr()),
+                &block.header,
+            ));
+            assert_eq!(
+                Block::project_tail_non_null(non_null).as_ptr(),
+                &block.elements as *const [u16] as *mut [u16],
+            );
+        }
+    }
+
+    #[test]
+    fn auto_traits() {
+        use core::panic::UnwindSafe;
+
+        #[crate::pointee(crate, auto_traits)]
+        trait Shape {
+            fn area(&self) -> u32;
+        }
+
+        #[crate::pointee(crate, auto_traits(Send, Sync, Unpin, UnwindSafe))]
+        trait Convert<T> {
+            fn convert(&self) -> T;
+        }
+
+        impl Shape for u32 {
+            fn area(&self) -> u32 {
+                *self * *self
+            }
+        }
+
+        impl Convert<u64> for u32 {
+            fn convert(&self) -> u64 {
+                u64::from(*self)
+            }
+        }
+
+        test_pointee(&3u32 as &dyn Shape);
+        test_pointee(&3u32 as &(dyn Shape + Send));
+        test_pointee(&3u32 as &(dyn Shape + Sync));
+        test_pointee(&3u32 as &(dyn Shape + Send + Sync));
+        let shape: &(dyn Shape + Send + Sync) = &3u32;
+        assert_eq!(shape.area(), 9);
+        assert_eq!(
+            <dyn Shape + Send + Sync as crate::PointeeKind>::KIND,
+            crate::MetadataKind::Dyn,
+        );
+
+        test_pointee(&4u32 as &(dyn Convert<u64> + Unpin));
+        test_pointee(&4u32 as &(dyn Convert<u64> + Send + UnwindSafe));
+        let ptr = crate::coerce_ptr::<
+            _,
+            dyn Convert<u64> + Send + Sync + Unpin + UnwindSafe,
+        >(&4u32);
+        // SAFETY: `ptr` points to a valid `u32`.
+        assert_eq!(unsafe { &*ptr }.convert(), 4);
+
+        // Trait objects with non-`'static` lifetime bounds implement `Pointee`
+        // too.
+        struct Borrowed<'a>(&'a u32);
+
+        impl Shape for Borrowed<'_> {
+            fn area(&self) -> u32 {
+                self.0.area()
+            }
+        }
+
+        let value = 5u32;
+        let borrowed = Borrowed(&value);
+        let shape: &(dyn Shape + Send + Sync + '_) = &borrowed;
+        test_pointee(shape);
+        assert_eq!(shape.area(), 25);
+    }
+
+    #[test]
+    fn associated_types() {
+        #[crate::pointee(crate)]
+        trait Source<T> {
+            type Item: Into<T>;
+            type Items: Iterator<Item = Self::Item>;
+            type Extra
+            where
+                Self: Sized;
+
+            fn next_item(&mut self) -> Option<Self::Item>;
+            fn items(&self) -> Self::Items;
+        }
+
+        #[crate::pointee(crate, auto_traits)]
+        trait Task {
+            type Output: ?Sized + ToString;
+
+            fn poll(&mut self) -> Option<&Self::Output>;
+        }
+
+        struct Countdown(u8);
+
+        impl Source<u32> for Countdown {
+            type Item = u8;
+            type Items = core::ops::Range<u8>;
+            type Extra = ();
+
+            fn next_item(&mut self) -> Option<u8> {
+                self.0 = self.0.checked_sub(1)?;
+                Some(self.0)
+            }
+
+            fn items(&self) -> Self::Items {
+                0..self.0
+            }
+        }
+
+        struct Ready(&'static str);
+
+        impl Task for Ready {
+            type Output = str;
+
+            fn poll(&mut self) -> Option<&str> {
+                Some(self.0)
+            }
+        }
+
+        test_pointee(&Countdown(2)
+            as &dyn Source<u32, Item = u8, Items = core::ops::Range<u8>>);
+        let mut countdown = Countdown(3);
+        let source: &mut dyn Source<
+            u32,
+            Item = u8,
+            Items = core::ops::Range<u8>,
+        > = &mut countdown;
+        assert_eq!(source.next_item(), Some(2));
+        assert_eq!(source.items().map(u32::from).sum::<u32>(), 1);
+
+        test_pointee(&Ready("done") as &(dyn Task<Output = str> + Send));
+        let ptr = crate::coerce_ptr::<_, dyn Task<Output = str> + Sync>(
+            &Ready("done"),
+        );
+        assert_eq!(
+            <dyn Task<Output = str> as crate::PointeeKind>::KIND,
+            crate::MetadataKind::Dyn
+        );
+        let mut task = Ready("done");
+        let task: &mut dyn Task<Output = str> = &mut task;
+        assert_eq!(task.poll().unwrap().to_string(), "done");
+        assert!(!ptr.is_null());
+    }
+
+    #[test]
+    fn upcast() {
+        use core::fmt::{Debug, Display};
+
+        use crate::Upcast;
+
+        #[crate::pointee(crate, auto_traits(Send), upcast(Debug, Any))]
+        trait Shape: Debug + Any {
+            fn sides(&self) -> u32;
+        }
+
+        #[crate::pointee(crate, upcast(Display, Source<T>))]
+        trait Labeled<T>: Display + Source<T> {}
+
+        #[crate::pointee(crate)]
+        trait Source<T> {
+            fn get(&self) -> T;
+        }
+
+        #[derive(Debug)]
+        struct Square(u32);
+
+        impl Shape for Square {
+            fn sides(&self) -> u32 {
+                4
+            }
+        }
+
+        impl Display for Square {
+            fn fmt(
+                &self,
+                f: &mut core::fmt::Formatter<'_>,
+            ) -> core::fmt::Result {
+                write!(f, "square of {}", self.0)
+            }
+        }
+
+        impl Source<u32> for Square {
+            fn get(&self) -> u32 {
+                self.0
+            }
+        }
+
+        impl Labeled<u32> for Square {}
+
+        let mut square = Square(2);
+        let shape: &mut (dyn Shape + Send) = &mut square;
+        assert_eq!(shape.sides(), 4);
+        let any: &mut (dyn Any + Send) = shape.upcast_mut();
+        any.downcast_mut::<Square>().unwrap().0 = 3;
+        let debug: &(dyn Debug + Send) = shape.upcast_ref();
+        assert_eq!(written_len(format_args!("{debug:?}")), "Square(3)".len());
+
+        let labeled: &dyn Labeled<u32> = &square;
+        let display: &dyn Display = labeled.upcast_ref();
+        assert_eq!(written_len(format_args!("{display}")), "square of 3".len());
+        let source: &dyn Source<u32> = labeled.upcast_ref();
+        assert_eq!(source.get(), 3);
+
+        #[cfg(ptr_meta_trait_upcasting)]
+        upcast_metadata(&square);
+
+        // Native trait upcasting is required to upcast metadata.
+        #[cfg(ptr_meta_trait_upcasting)]
+        fn upcast_metadata(square: &Square) {
+            use crate::{metadata, to_raw_parts, UpcastMetadata};
+
+            let shape: &dyn Shape = square;
+            let debug =
+                <dyn Shape as UpcastMetadata<dyn Debug>>::upcast_metadata(
+                    metadata(shape),
+                );
+            assert_eq!(debug.layout(), core::alloc::Layout::new::<Square>());
+
+            let ptr = core::ptr::null::<Square>() as *const dyn Shape;
+            let any: *const dyn Any = <dyn Shape>::upcast_ptr(ptr);
+            assert!(any.is_null());
+
+            let labeled: &dyn Labeled<u32> = square;
+            let source: *const dyn Source<u32> =
+                <dyn Labeled<u32>>::upcast_ptr(labeled);
+            assert_eq!(
+                to_raw_parts(source).0,
+                (labeled as *const dyn Labeled<u32>).cast(),
+            );
+            // SAFETY: `source` points to `square`.
+            assert_eq!(unsafe { &*source }.get(), 3);
+        }
+
+        // Returns the length of the formatted arguments without allocating.
+        fn written_len(args: core::fmt::Arguments<'_>) -> usize {
+            struct Counter(usize);
+
+            impl core::fmt::Write for Counter {
+                fn write_str(&mut self, s: &str) -> core::fmt::Result {
+                    self.0 += s.len();
+                    Ok(())
+                }
+            }
+
+            let mut counter = Counter(0);
+            core::fmt::write(&mut counter, args).unwrap();
+            counter.0
+        }
+    }
+
     #[test]
     fn generic_trait() {
         #[allow(dead_code)]
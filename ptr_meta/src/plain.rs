@@ -0,0 +1,197 @@
+use core::mem::size_of;
+
+use crate::{metadata, LayoutFromMetadata};
+
+/// A type which can be read from and written to plain bytes.
+///
+/// This is implemented for integers, floats, `bool`, `char`, arrays and slices
+/// of `Plain` types, and `str`. `#[derive(Pointee)]` implements it for
+/// `#[repr(C)]` and `#[repr(transparent)]` structs with the
+/// `#[ptr_meta(plain)]` attribute when every field is `Plain`.
+///
+/// # Safety
+///
+/// `is_valid` must only return `true` if the bytes it is given are a valid
+/// value of `Self`.
+pub unsafe trait Plain: LayoutFromMetadata {
+    /// Returns whether the bytes pointed to by `ptr` are a valid value of
+    /// `Self`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be aligned for `Self` and point to initialized bytes of the
+    /// layout given by its metadata.
+    unsafe fn is_valid(ptr: *const Self) -> bool;
+
+    /// Writes the bytes of `self` to `out`.
+    ///
+    /// `out` is zeroed and is exactly the size of `self`. Any bytes which are
+    /// not part of a field, like padding, are left as zeroes.
+    fn write_bytes(&self, out: &mut [u8]);
+}
+
+macro_rules! impl_plain_numbers {
+    ($($ty:ty),*) => {
+        $(
+            // SAFETY: Every bit pattern is a valid number.
+            unsafe impl Plain for $ty {
+                #[inline]
+                unsafe fn is_valid(_: *const Self) -> bool {
+                    true
+                }
+
+                #[inline]
+                fn write_bytes(&self, out: &mut [u8]) {
+                    out.copy_from_slice(&self.to_ne_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_plain_numbers!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64
+);
+
+// SAFETY: Only `0` and `1` are accepted, which are the valid values of `bool`.
+unsafe impl Plain for bool {
+    #[inline]
+    unsafe fn is_valid(ptr: *const Self) -> bool {
+        // SAFETY: The caller has guaranteed that `ptr` points to an
+        // initialized byte.
+        unsafe { ptr.cast::<u8>().read() <= 1 }
+    }
+
+    #[inline]
+    fn write_bytes(&self, out: &mut [u8]) {
+        out[0] = *self as u8;
+    }
+}
+
+// SAFETY: Only valid Unicode scalar values are accepted.
+unsafe impl Plain for char {
+    #[inline]
+    unsafe fn is_valid(ptr: *const Self) -> bool {
+        // SAFETY: The caller has guaranteed that `ptr` is aligned and points
+        // to four initialized bytes.
+        let value = unsafe { ptr.cast::<u32>().read() };
+        char::from_u32(value).is_some()
+    }
+
+    #[inline]
+    fn write_bytes(&self, out: &mut [u8]) {
+        out.copy_from_slice(&(*self as u32).to_ne_bytes());
+    }
+}
+
+// SAFETY: An array is valid if each of its elements is valid.
+unsafe impl<T: Plain, const N: usize> Plain for [T; N] {
+    #[inline]
+    unsafe fn is_valid(ptr: *const Self) -> bool {
+        // SAFETY: The caller has guaranteed that `ptr` points to `N`
+        // initialized elements.
+        unsafe { is_valid_elements(ptr.cast::<T>(), N) }
+    }
+
+    #[inline]
+    fn write_bytes(&self, out: &mut [u8]) {
+        write_elements(self, out);
+    }
+}
+
+// SAFETY: A slice is valid if each of its elements is valid.
+unsafe impl<T: Plain> Plain for [T] {
+    #[inline]
+    unsafe fn is_valid(ptr: *const Self) -> bool {
+        // SAFETY: The caller has guaranteed that `ptr` points to `len`
+        // initialized elements.
+        unsafe { is_valid_elements(ptr.cast::<T>(), metadata(ptr)) }
+    }
+
+    #[inline]
+    fn write_bytes(&self, out: &mut [u8]) {
+        write_elements(self, out);
+    }
+}
+
+// SAFETY: Only valid UTF-8 is accepted.
+unsafe impl Plain for str {
+    #[inline]
+    unsafe fn is_valid(ptr: *const Self) -> bool {
+        // SAFETY: The caller has guaranteed that `ptr` points to `len`
+        // initialized bytes.
+        let bytes = unsafe {
+            core::slice::from_raw_parts(ptr.cast::<u8>(), metadata(ptr))
+        };
+        core::str::from_utf8(bytes).is_ok()
+    }
+
+    #[inline]
+    fn write_bytes(&self, out: &mut [u8]) {
+        out.copy_from_slice(self.as_bytes());
+    }
+}
+
+/// # Safety
+///
+/// `ptr` must be aligned for `T` and point to `len` initialized elements.
+#[inline]
+unsafe fn is_valid_elements<T: Plain>(ptr: *const T, len: usize) -> bool {
+    // SAFETY: Each element is in bounds, aligned, and initialized.
+    (0..len).all(|i| unsafe { T::is_valid(ptr.add(i)) })
+}
+
+#[inline]
+fn write_elements<T: Plain>(elements: &[T], out: &mut [u8]) {
+    if size_of::<T>() != 0 {
+        for (element, out) in
+            elements.iter().zip(out.chunks_exact_mut(size_of::<T>()))
+        {
+            element.write_bytes(out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Plain;
+
+    fn round_trip<T: Plain + ?Sized>(value: &T) -> bool {
+        let mut bytes = [0u64; 4];
+        // SAFETY: `bytes` is valid for reads and writes of 32 bytes.
+        let out = unsafe {
+            core::slice::from_raw_parts_mut(
+                bytes.as_mut_ptr().cast::<u8>(),
+                core::mem::size_of_val(value),
+            )
+        };
+        value.write_bytes(out);
+        // SAFETY: `bytes` is aligned to 8 bytes and initialized, and the
+        // pointer has the same metadata as `value`.
+        unsafe {
+            T::is_valid(crate::from_raw_parts(
+                bytes.as_ptr().cast(),
+                crate::metadata(value),
+            ))
+        }
+    }
+
+    #[test]
+    fn validation() {
+        assert!(round_trip(&12345u32));
+        assert!(round_trip(&[true, false]));
+        assert!(round_trip("héllo"));
+        assert!(round_trip(&['a', 'ß'][..]));
+
+        let byte = 2u8;
+        // SAFETY: `byte` is an initialized byte.
+        assert!(!unsafe { bool::is_valid((&byte as *const u8).cast()) });
+        let invalid = [0xffu8, 0xfe];
+        let invalid = crate::from_raw_parts::<str>(invalid.as_ptr().cast(), 2);
+        // SAFETY: `invalid` points to two initialized bytes.
+        assert!(!unsafe { str::is_valid(invalid) });
+        let surrogate = 0xd800u32;
+        // SAFETY: `surrogate` is four aligned and initialized bytes.
+        assert!(!unsafe { char::is_valid((&surrogate as *const u32).cast()) });
+    }
+}
@@ -0,0 +1,462 @@
+//! Length-prefixed records of [`Plain`] DSTs.
+//!
+//! Records are encoded in native byte order. [`Plain`] payloads are copied as
+//! they are laid out in memory, so length prefixes use the same byte order to
+//! keep the format consistent. Records written on a target with one byte order
+//! must not be read on a target with another.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::{
+    convert::Infallible, error::Error, fmt, iter::FusedIterator,
+    marker::PhantomData,
+};
+
+use crate::{from_raw_parts, Plain, ValidateMetadata};
+#[cfg(feature = "alloc")]
+use crate::{metadata, Pointee};
+
+/// An unsigned integer type which encodes the length prefix of a record.
+///
+/// Length prefixes are encoded in native byte order.
+pub trait LengthPrefix {
+    /// The size of the length prefix in bytes.
+    const SIZE: usize;
+
+    /// Decodes a length from the first `SIZE` bytes of `bytes`, returning
+    /// `None` if it does not fit in a `usize`.
+    fn decode(bytes: &[u8]) -> Option<usize>;
+
+    /// Encodes a length into the first `SIZE` bytes of `out`, returning
+    /// `false` if it does not fit in `Self`.
+    fn encode(len: usize, out: &mut [u8]) -> bool;
+}
+
+macro_rules! impl_length_prefix {
+    ($($ty:ty),*) => {
+        $(
+            impl LengthPrefix for $ty {
+                const SIZE: usize = core::mem::size_of::<$ty>();
+
+                #[inline]
+                fn decode(bytes: &[u8]) -> Option<usize> {
+                    let mut ne_bytes = [0; Self::SIZE];
+                    ne_bytes.copy_from_slice(&bytes[..Self::SIZE]);
+                    usize::try_from(<$ty>::from_ne_bytes(ne_bytes)).ok()
+                }
+
+                #[inline]
+                fn encode(len: usize, out: &mut [u8]) -> bool {
+                    let Ok(len) = <$ty>::try_from(len) else {
+                        return false;
+                    };
+                    out[..Self::SIZE].copy_from_slice(&len.to_ne_bytes());
+                    true
+                }
+            }
+        )*
+    };
+}
+
+impl_length_prefix!(u8, u16, u32, u64);
+
+/// An error which occurs while reading or writing records.
+///
+/// `E` is the error returned when the metadata of a record fails
+/// [validation](ValidateMetadata).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordError<E = Infallible> {
+    /// The bytes ended in the middle of a record.
+    Truncated,
+    /// The length of a record does not fit in the length prefix, or is too
+    /// large for the record type.
+    InvalidLength,
+    /// The payload of a record is not properly aligned in memory.
+    Misaligned,
+    /// The payload of a record is not a valid value of the record type.
+    InvalidValue,
+    /// The length of a record failed validation.
+    InvalidMetadata(E),
+}
+
+impl<E: fmt::Display> fmt::Display for RecordError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "the record was truncated"),
+            Self::InvalidLength => write!(f, "the record length was invalid"),
+            Self::Misaligned => write!(f, "the record payload was misaligned"),
+            Self::InvalidValue => write!(f, "the record payload was invalid"),
+            Self::InvalidMetadata(e) => {
+                write!(f, "the record length failed validation: {e}")
+            }
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for RecordError<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidMetadata(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[inline]
+fn align_up(position: usize, align: usize) -> Option<usize> {
+    Some(position.checked_add(align - 1)? & !(align - 1))
+}
+
+/// An iterator over records in a byte buffer.
+///
+/// Each record is a native-endian length prefix of type `P` followed by the
+/// payload. The payload starts at the next offset from the start of the buffer
+/// which is aligned for `T`, and its bytes are interpreted as a `T` whose
+/// metadata is the length. `DstWriter` writes records in this format.
+///
+/// Because padding is computed relative to the start of the buffer, the
+/// buffer must be at least as aligned as the records in it. The iterator
+/// yields an error and stops if a record is truncated, has an invalid length,
+/// fails [validation](ValidateMetadata), is misaligned in memory, or is not a
+/// valid `T`.
+///
+/// # Example
+///
+/// ```
+/// use ptr_meta::{DstReader, DstWriter};
+///
+/// let mut writer = DstWriter::<[u32], u8>::new();
+/// writer.push(&[1, 2, 3]).unwrap();
+/// writer.push(&[4]).unwrap();
+///
+/// // Copy the records into a buffer which is aligned for `u32`.
+/// let mut aligned = [0u32; 8];
+/// let bytes = writer.as_bytes();
+/// let buffer = unsafe {
+///     core::slice::from_raw_parts_mut(aligned.as_mut_ptr().cast::<u8>(), 32)
+/// };
+/// buffer[..bytes.len()].copy_from_slice(bytes);
+///
+/// let mut reader = DstReader::<[u32], u8>::new(&buffer[..bytes.len()]);
+/// assert_eq!(reader.next(), Some(Ok(&[1, 2, 3][..])));
+/// assert_eq!(reader.next(), Some(Ok(&[4][..])));
+/// assert_eq!(reader.next(), None);
+/// ```
+pub struct DstReader<'a, T, P = u32>
+where
+    T: Plain + ValidateMetadata<Metadata = usize> + ?Sized,
+    P: LengthPrefix,
+{
+    bytes: &'a [u8],
+    position: usize,
+    _phantom: PhantomData<(&'a T, P)>,
+}
+
+impl<'a, T, P> DstReader<'a, T, P>
+where
+    T: Plain + ValidateMetadata<Metadata = usize> + ?Sized,
+    P: LengthPrefix,
+{
+    /// Returns a reader over the records in `bytes`.
+    #[inline]
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            position: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the bytes which have not been read yet.
+    #[inline]
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.position..]
+    }
+
+    fn read_record(&self) -> Result<(&'a T, usize), RecordError<T::Error>> {
+        let prefix_end = self
+            .position
+            .checked_add(P::SIZE)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or(RecordError::Truncated)?;
+        let len = P::decode(&self.bytes[self.position..prefix_end])
+            .ok_or(RecordError::InvalidLength)?;
+        T::validate_metadata(len).map_err(RecordError::InvalidMetadata)?;
+        let layout =
+            T::layout_for_metadata(len).ok_or(RecordError::InvalidLength)?;
+
+        let start = align_up(prefix_end, layout.align())
+            .ok_or(RecordError::Truncated)?;
+        let end = start
+            .checked_add(layout.size())
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or(RecordError::Truncated)?;
+
+        let data_address = self.bytes[start..end].as_ptr();
+        if data_address as usize & (layout.align() - 1) != 0 {
+            return Err(RecordError::Misaligned);
+        }
+        let ptr = from_raw_parts::<T>(data_address.cast(), len);
+        // SAFETY: `ptr` is aligned and points to `layout.size()` initialized
+        // bytes, which is the size of a `T` with metadata `len`.
+        if !unsafe { T::is_valid(ptr) } {
+            return Err(RecordError::InvalidValue);
+        }
+
+        // SAFETY: The bytes are a valid `T` and are borrowed for `'a`.
+        Ok((unsafe { &*ptr }, end))
+    }
+}
+
+impl<'a, T, P> Iterator for DstReader<'a, T, P>
+where
+    T: Plain + ValidateMetadata<Metadata = usize> + ?Sized,
+    P: LengthPrefix,
+{
+    type Item = Result<&'a T, RecordError<T::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position == self.bytes.len() {
+            return None;
+        }
+
+        match self.read_record() {
+            Ok((record, end)) => {
+                self.position = end;
+                Some(Ok(record))
+            }
+            Err(e) => {
+                self.position = self.bytes.len();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<T, P> FusedIterator for DstReader<'_, T, P>
+where
+    T: Plain + ValidateMetadata<Metadata = usize> + ?Sized,
+    P: LengthPrefix,
+{
+}
+
+/// A buffer which records can be appended to.
+///
+/// See [`DstReader`] for the format of the records.
+#[cfg(feature = "alloc")]
+pub struct DstWriter<T, P = u32>
+where
+    T: Plain + Pointee<Metadata = usize> + ?Sized,
+    P: LengthPrefix,
+{
+    bytes: Vec<u8>,
+    _phantom: PhantomData<fn(&T, P)>,
+}
+
+#[cfg(feature = "alloc")]
+impl<T, P> DstWriter<T, P>
+where
+    T: Plain + Pointee<Metadata = usize> + ?Sized,
+    P: LengthPrefix,
+{
+    /// Returns a new, empty writer.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Appends a record containing `value`.
+    ///
+    /// Returns an error if the length of `value` does not fit in `P`.
+    pub fn push(&mut self, value: &T) -> Result<(), RecordError> {
+        let len = metadata(value);
+        let layout = core::alloc::Layout::for_value(value);
+
+        let prefix_start = self.bytes.len();
+        let start = prefix_start
+            .checked_add(P::SIZE)
+            .and_then(|end| align_up(end, layout.align()))
+            .ok_or(RecordError::InvalidLength)?;
+        let end = start
+            .checked_add(layout.size())
+            .ok_or(RecordError::InvalidLength)?;
+
+        self.bytes.resize(end, 0);
+        if !P::encode(len, &mut self.bytes[prefix_start..]) {
+            self.bytes.truncate(prefix_start);
+            return Err(RecordError::InvalidLength);
+        }
+        value.write_bytes(&mut self.bytes[start..end]);
+        Ok(())
+    }
+
+    /// Returns the bytes of the records written so far.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the bytes of the records written so far, consuming the writer.
+    #[inline]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T, P> Default for DstWriter<T, P>
+where
+    T: Plain + Pointee<Metadata = usize> + ?Sized,
+    P: LengthPrefix,
+{
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use alloc::vec::Vec;
+    use core::convert::Infallible;
+
+    use super::{DstReader, DstWriter, LengthPrefix, RecordError};
+    use crate::Plain;
+
+    /// Copies `bytes` into a buffer which is aligned to 8 bytes.
+    fn aligned(bytes: &[u8]) -> Vec<u64> {
+        let mut buffer = alloc::vec![0u64; bytes.len().div_ceil(8) + 1];
+        // SAFETY: `buffer` is valid for writes of at least `bytes.len()`
+        // bytes.
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                buffer.as_mut_ptr().cast::<u8>(),
+                bytes.len(),
+            );
+        }
+        buffer
+    }
+
+    fn as_bytes(buffer: &[u64], len: usize) -> &[u8] {
+        // SAFETY: `buffer` is valid for reads of at least `len` bytes.
+        unsafe { core::slice::from_raw_parts(buffer.as_ptr().cast(), len) }
+    }
+
+    fn read_all<T, P>(bytes: &[u8]) -> Vec<Result<&T, RecordError>>
+    where
+        T: Plain
+            + crate::ValidateMetadata<Metadata = usize, Error = Infallible>
+            + ?Sized,
+        P: LengthPrefix,
+    {
+        DstReader::<T, P>::new(bytes).collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut writer = DstWriter::<str, u16>::new();
+        writer.push("hello").unwrap();
+        writer.push("").unwrap();
+        writer.push("wörld").unwrap();
+        let bytes = writer.as_bytes();
+        assert_eq!(&bytes[..7], b"\x05\x00hello");
+
+        let strings = read_all::<str, u16>(bytes);
+        assert_eq!(strings, [Ok("hello"), Ok(""), Ok("wörld")]);
+
+        let mut writer = DstWriter::<[u32]>::default();
+        writer.push(&[1, 2]).unwrap();
+        writer.push(&[3]).unwrap();
+        let bytes = writer.into_bytes();
+        assert_eq!(bytes.len(), 4 + 8 + 4 + 4);
+        let buffer = aligned(&bytes);
+        let mut reader =
+            DstReader::<[u32]>::new(as_bytes(&buffer, bytes.len()));
+        assert_eq!(reader.next(), Some(Ok(&[1, 2][..])));
+        assert_eq!(reader.next(), Some(Ok(&[3][..])));
+        assert_eq!(reader.next(), None);
+    }
+
+    #[test]
+    fn errors() {
+        // Truncated prefix and payload.
+        assert_eq!(
+            read_all::<str, u16>(b"\x05"),
+            [Err(RecordError::Truncated)]
+        );
+        assert_eq!(
+            read_all::<str, u16>(b"\x05\x00hell"),
+            [Err(RecordError::Truncated)]
+        );
+
+        // Invalid UTF-8 stops iteration.
+        assert_eq!(
+            read_all::<str, u16>(b"\x01\x00\xff\x01\x00a"),
+            [Err(RecordError::InvalidValue)]
+        );
+
+        // A length which overflows the layout of the record type.
+        let mut writer = DstWriter::<[u64], u64>::new();
+        writer.push(&[7]).unwrap();
+        let mut bytes = writer.into_bytes();
+        bytes[..8].copy_from_slice(&u64::MAX.to_ne_bytes());
+        let buffer = aligned(&bytes);
+        let mut reader =
+            DstReader::<[u64], u64>::new(as_bytes(&buffer, bytes.len()));
+        assert_eq!(reader.next(), Some(Err(RecordError::InvalidLength)));
+        assert_eq!(reader.next(), None);
+
+        // A payload which is misaligned in memory.
+        let mut writer = DstWriter::<[u32], u32>::new();
+        writer.push(&[1]).unwrap();
+        let bytes = writer.into_bytes();
+        let buffer = aligned(&[&[0][..], &bytes].concat());
+        let misaligned = &as_bytes(&buffer, bytes.len() + 1)[1..];
+        let mut reader = DstReader::<[u32], u32>::new(misaligned);
+        assert_eq!(reader.next(), Some(Err(RecordError::Misaligned)));
+
+        // A length which does not fit in the prefix.
+        let mut writer = DstWriter::<[u8], u8>::new();
+        assert_eq!(writer.push(&[0; 256]), Err(RecordError::InvalidLength));
+        assert!(writer.as_bytes().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derived_records() {
+        use crate::{DstBuilder, Pointee};
+
+        #[derive(Pointee, Debug, PartialEq)]
+        #[ptr_meta(crate, plain)]
+        #[repr(C)]
+        struct Record {
+            kind: u8,
+            flag: bool,
+            values: [u32],
+        }
+
+        let first: alloc::boxed::Box<Record> =
+            DstBuilder::new((3, true)).build_from_slice(&[10, 20]);
+        let second: alloc::boxed::Box<Record> =
+            DstBuilder::new((4, false)).build_from_slice(&[]);
+
+        let mut writer = DstWriter::<Record, u8>::new();
+        writer.push(&first).unwrap();
+        writer.push(&second).unwrap();
+        let mut bytes = writer.into_bytes();
+
+        let buffer = aligned(&bytes);
+        let records = read_all::<Record, u8>(as_bytes(&buffer, bytes.len()));
+        assert_eq!(records, [Ok(&*first), Ok(&*second)]);
+
+        // Corrupt the `flag` of the first record.
+        bytes[5] = 2;
+        let buffer = aligned(&bytes);
+        let records = read_all::<Record, u8>(as_bytes(&buffer, bytes.len()));
+        assert_eq!(records, [Err(RecordError::InvalidValue)]);
+    }
+}
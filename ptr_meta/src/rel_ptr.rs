@@ -0,0 +1,408 @@
+use core::{error::Error, fmt, marker::PhantomData, mem::MaybeUninit};
+
+use crate::{
+    from_raw_parts, from_raw_parts_mut, tagged::addr, to_raw_parts, Pointee,
+};
+
+/// A signed integer type which can store the offset of a [`RelPtr`].
+pub trait RelOffset: Copy + Eq + fmt::Debug {
+    /// Narrows an offset to this type, returning `None` if it does not fit.
+    fn from_isize(offset: isize) -> Option<Self>;
+
+    /// Widens this value back to an offset.
+    fn to_isize(self) -> isize;
+}
+
+macro_rules! impl_rel_offset {
+    ($($ty:ty),*) => {
+        $(
+            impl RelOffset for $ty {
+                #[inline]
+                fn from_isize(offset: isize) -> Option<Self> {
+                    Self::try_from(offset).ok()
+                }
+
+                #[inline]
+                fn to_isize(self) -> isize {
+                    // Only implemented for types no wider than `isize`.
+                    self as isize
+                }
+            }
+        )*
+    };
+}
+
+impl_rel_offset!(i16);
+#[cfg(any(target_pointer_width = "32", target_pointer_width = "64"))]
+impl_rel_offset!(i32);
+#[cfg(target_pointer_width = "64")]
+impl_rel_offset!(i64);
+
+/// An error which occurs when a [`RelPtr`] cannot point to its target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OffsetError {
+    offset: isize,
+}
+
+impl OffsetError {
+    /// Returns the offset which could not be stored.
+    #[inline]
+    pub fn offset(&self) -> isize {
+        self.offset
+    }
+}
+
+impl fmt::Display for OffsetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.offset == 0 {
+            write!(f, "a relative pointer cannot point to itself")
+        } else {
+            write!(
+                f,
+                "offset {} is out of range for the relative pointer",
+                self.offset,
+            )
+        }
+    }
+}
+
+impl Error for OffsetError {}
+
+/// A self-relative pointer to a possibly-unsized value.
+///
+/// A `RelPtr` stores the signed offset from its own address to the data
+/// address of its target along with the target's pointer metadata. Because it
+/// doesn't store any absolute addresses, a structure containing `RelPtr`s can
+/// be moved as a whole (for example by memory-mapping a file) and still point
+/// to the right places.
+///
+/// The offset is stored as an `O`, which is `i32` by default and may also be
+/// `i16` or `i64`. Only offset types which are no wider than `isize` implement
+/// [`RelOffset`], so `i64` is only available on 64-bit targets and `i32` is
+/// not available on 16-bit targets. An offset of zero represents a null
+/// pointer.
+///
+/// The target must be in the same allocation as the `RelPtr`. The pointer to
+/// the target is derived from a pointer to the `RelPtr`, so
+/// [`as_ptr`](RelPtr::as_ptr) and [`as_mut_ptr`](RelPtr::as_mut_ptr) take a
+/// raw pointer which must be valid for that whole allocation. A pointer
+/// derived from a reference to just the `RelPtr` is not enough.
+///
+/// # Example
+///
+/// ```
+/// use core::mem::MaybeUninit;
+///
+/// use ptr_meta::RelPtr;
+///
+/// #[repr(C)]
+/// struct Buffer {
+///     ptr: MaybeUninit<RelPtr<str, i16>>,
+///     text: [u8; 5],
+/// }
+///
+/// let mut buffer = Buffer {
+///     ptr: MaybeUninit::uninit(),
+///     text: *b"hello",
+/// };
+/// let text = core::str::from_utf8(&buffer.text).unwrap() as *const str;
+/// let ptr = RelPtr::emplace(text, &mut buffer.ptr);
+/// assert!(!ptr.is_null());
+///
+/// // `ptr` is the first field of `Buffer`, so this points to it with the
+/// // provenance of the whole buffer.
+/// let this = (&buffer as *const Buffer).cast::<RelPtr<str, i16>>();
+/// // SAFETY: `this` points to an initialized `RelPtr` and is valid for all of
+/// // `buffer`, which contains the target.
+/// assert_eq!(unsafe { &*RelPtr::as_ptr(this) }, "hello");
+/// ```
+#[repr(C)]
+pub struct RelPtr<T: Pointee + ?Sized, O: RelOffset = i32> {
+    offset: O,
+    metadata: T::Metadata,
+    _phantom: PhantomData<*const T>,
+}
+
+impl<T: Pointee + ?Sized, O: RelOffset> RelPtr<T, O> {
+    /// Returns a null relative pointer with the given metadata.
+    #[inline]
+    pub fn null_with_metadata(metadata: T::Metadata) -> Self {
+        Self {
+            // Zero always fits in the offset type.
+            offset: O::from_isize(0).unwrap(),
+            metadata,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns a null relative pointer.
+    #[inline]
+    pub fn null() -> Self
+    where
+        T::Metadata: Default,
+    {
+        Self::null_with_metadata(T::Metadata::default())
+    }
+
+    /// Writes a relative pointer to `target` into `out`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the offset from `out` to `target` does not fit in `O` or is
+    /// zero.
+    #[inline]
+    pub fn emplace(target: *const T, out: &mut MaybeUninit<Self>) -> &mut Self {
+        match Self::try_emplace(target, out) {
+            Ok(ptr) => ptr,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Writes a relative pointer to `target` into `out`, or returns an error
+    /// if the offset from `out` to `target` does not fit in `O` or is zero.
+    pub fn try_emplace(
+        target: *const T,
+        out: &mut MaybeUninit<Self>,
+    ) -> Result<&mut Self, OffsetError> {
+        let (data_address, metadata) = to_raw_parts(target);
+        let target_addr = addr(data_address.cast_mut());
+        let this_addr = addr(out.as_mut_ptr().cast());
+        // Offsets which don't fit in an `isize` are reported as the nearest
+        // `isize` instead.
+        let offset = if target_addr >= this_addr {
+            0isize
+                .checked_add_unsigned(target_addr - this_addr)
+                .ok_or(OffsetError { offset: isize::MAX })?
+        } else {
+            0isize
+                .checked_sub_unsigned(this_addr - target_addr)
+                .ok_or(OffsetError { offset: isize::MIN })?
+        };
+        if offset == 0 {
+            return Err(OffsetError { offset });
+        }
+        let offset = O::from_isize(offset).ok_or(OffsetError { offset })?;
+
+        Ok(out.write(Self {
+            offset,
+            metadata,
+            _phantom: PhantomData,
+        }))
+    }
+
+    /// Returns the offset from this pointer to its target.
+    #[inline]
+    pub fn offset(&self) -> isize {
+        self.offset.to_isize()
+    }
+
+    /// Returns the metadata of the target.
+    #[inline]
+    pub fn metadata(&self) -> T::Metadata {
+        self.metadata
+    }
+
+    /// Returns whether the pointer is null.
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        self.offset() == 0
+    }
+
+    /// Returns a pointer to the target of the relative pointer at `this`.
+    ///
+    /// This takes a raw pointer instead of `&self` because a pointer derived
+    /// from a reference to a `RelPtr` only has provenance for the `RelPtr`
+    /// itself, and could not be used to access its target. The returned
+    /// pointer is derived from `this`, so it may only be dereferenced if `this`
+    /// is valid for the allocation which contains both the relative pointer
+    /// and its target. If the relative pointer is null, the returned pointer
+    /// has a null data address.
+    ///
+    /// # Safety
+    ///
+    /// `this` must be properly aligned, valid for reads, and point to an
+    /// initialized `RelPtr`.
+    #[inline]
+    pub unsafe fn as_ptr(this: *const Self) -> *const T {
+        // SAFETY: The caller has guaranteed that `this` points to an
+        // initialized `RelPtr` which is valid for reads.
+        let (offset, metadata) =
+            unsafe { ((*this).offset(), (*this).metadata) };
+        let data_address = if offset == 0 {
+            core::ptr::null()
+        } else {
+            this.cast::<u8>().wrapping_offset(offset).cast()
+        };
+        from_raw_parts(data_address, metadata)
+    }
+
+    /// Returns a mutable pointer to the target of the relative pointer at
+    /// `this`.
+    ///
+    /// This takes a raw pointer instead of `&self` because a pointer derived
+    /// from a reference to a `RelPtr` only has provenance for the `RelPtr`
+    /// itself, and could not be used to access its target. The returned
+    /// pointer is derived from `this`, so it may only be dereferenced if `this`
+    /// is valid for the allocation which contains both the relative pointer
+    /// and its target. If the relative pointer is null, the returned pointer
+    /// has a null data address.
+    ///
+    /// # Safety
+    ///
+    /// `this` must be properly aligned, valid for reads, and point to an
+    /// initialized `RelPtr`.
+    #[inline]
+    pub unsafe fn as_mut_ptr(this: *mut Self) -> *mut T {
+        // SAFETY: The caller has guaranteed that `this` points to an
+        // initialized `RelPtr` which is valid for reads.
+        let (offset, metadata) =
+            unsafe { ((*this).offset(), (*this).metadata) };
+        let data_address = if offset == 0 {
+            core::ptr::null_mut()
+        } else {
+            this.cast::<u8>().wrapping_offset(offset).cast()
+        };
+        from_raw_parts_mut(data_address, metadata)
+    }
+}
+
+impl<T: Pointee + ?Sized, O: RelOffset> fmt::Debug for RelPtr<T, O>
+where
+    T::Metadata: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RelPtr")
+            .field("offset", &self.offset())
+            .field("metadata", &self.metadata)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::MaybeUninit;
+
+    use super::{RelOffset, RelPtr};
+    use crate::Pointee;
+
+    #[repr(C)]
+    struct Buffer<T: Pointee + ?Sized, O: RelOffset, const N: usize> {
+        ptr: MaybeUninit<RelPtr<T, O>>,
+        padding: [u8; N],
+        values: [u32; 4],
+    }
+
+    impl<T: Pointee + ?Sized, O: RelOffset, const N: usize> Buffer<T, O, N> {
+        fn new() -> Self {
+            Self {
+                ptr: MaybeUninit::uninit(),
+                padding: [0; N],
+                values: [1, 2, 3, 4],
+            }
+        }
+
+        // Returns a pointer to the relative pointer which is valid for the
+        // whole buffer.
+        fn rel_ptr(&mut self) -> *mut RelPtr<T, O> {
+            (self as *mut Self).cast()
+        }
+    }
+
+    #[test]
+    fn slices() {
+        let mut buffer = Buffer::<[u32], i16, 0>::new();
+        let values = &buffer.values[1..] as *const [u32];
+        let ptr = RelPtr::emplace(values, &mut buffer.ptr);
+        assert!(!ptr.is_null());
+        assert_eq!(ptr.metadata(), 3);
+
+        let this = buffer.rel_ptr();
+        // SAFETY: `this` points to the emplaced pointer and is valid for all
+        // of `buffer`, which contains the target.
+        assert_eq!(unsafe { &*RelPtr::as_ptr(this) }, &[2, 3, 4]);
+        // SAFETY: `this` points to the emplaced pointer and is valid for all
+        // of `buffer`, which contains the target.
+        unsafe {
+            (*RelPtr::as_mut_ptr(this))[0] = 20;
+        }
+        assert_eq!(buffer.values[1], 20);
+    }
+
+    #[test]
+    fn null_and_overflow() {
+        let null = RelPtr::<[u8], i32>::null();
+        assert!(null.is_null());
+        // SAFETY: `null` is an initialized `RelPtr`.
+        let ptr = unsafe { RelPtr::as_ptr(&null) };
+        assert!(ptr.cast::<u8>().is_null());
+
+        let mut buffer = Buffer::<u32, i16, { 1 << 15 }>::new();
+        let target = &buffer.values[0] as *const u32;
+        let error = RelPtr::<u32, i16>::try_emplace(target, &mut buffer.ptr)
+            .unwrap_err();
+        assert!(error.offset() > i16::MAX as isize);
+        assert!(RelPtr::<u32, i32>::try_emplace(
+            target,
+            // SAFETY: `RelPtr<u32, i32>` and `RelPtr<u32, i16>` are both valid
+            // when uninitialized.
+            unsafe { &mut *buffer.ptr.as_mut_ptr().cast() },
+        )
+        .is_ok());
+
+        let mut slot = MaybeUninit::<RelPtr<(), i16>>::uninit();
+        let target = slot.as_ptr().cast::<()>();
+        assert_eq!(
+            RelPtr::try_emplace(target, &mut slot).unwrap_err().offset(),
+            0
+        );
+    }
+
+    #[test]
+    fn backwards() {
+        #[repr(C)]
+        struct Backwards {
+            value: u32,
+            ptr: MaybeUninit<RelPtr<u32, i16>>,
+        }
+
+        let mut buffer = Backwards {
+            value: 7,
+            ptr: MaybeUninit::uninit(),
+        };
+        let target = &buffer.value as *const u32;
+        let ptr = RelPtr::emplace(target, &mut buffer.ptr);
+        assert_eq!(ptr.offset(), -4);
+
+        let this = (&mut buffer as *mut Backwards)
+            .cast::<u8>()
+            .wrapping_add(4)
+            .cast::<RelPtr<u32, i16>>();
+        // SAFETY: `this` points to the emplaced pointer and is valid for all
+        // of `buffer`, which contains the target.
+        assert_eq!(unsafe { *RelPtr::as_ptr(this) }, 7);
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derived_tail() {
+        use crate::from_raw_parts;
+
+        #[derive(Pointee)]
+        #[ptr_meta(crate)]
+        #[repr(C)]
+        struct Block {
+            header: u32,
+            elements: [u32],
+        }
+
+        let mut buffer = Buffer::<Block, i32, 0>::new();
+        let block = from_raw_parts::<Block>(buffer.values.as_ptr().cast(), 3);
+        RelPtr::emplace(block, &mut buffer.ptr);
+        // SAFETY: The pointer was just emplaced and points to
+        // `buffer.values`, which has the layout of a `Block` with three
+        // elements.
+        let block = unsafe { &*RelPtr::as_ptr(buffer.rel_ptr()) };
+        assert_eq!(block.header, 1);
+        assert_eq!(block.elements, [2, 3, 4]);
+    }
+}
@@ -0,0 +1,413 @@
+#[cfg(feature = "alloc")]
+use alloc::{
+    alloc::{alloc, dealloc, handle_alloc_error},
+    boxed::Box,
+};
+use core::alloc::Layout;
+#[cfg(feature = "alloc")]
+use core::{marker::PhantomData, ptr};
+
+#[cfg(feature = "alloc")]
+use crate::{from_raw_parts_mut, ValidateMetadata};
+use crate::{Pointee, SliceLikePointee};
+
+/// A [`SliceLikePointee`] which is laid out as an array of its elements.
+///
+/// This is implemented for slices and `str`. `DstBuilder` relies on this to
+/// write the tail of a [`SliceDst`] one element at a time.
+///
+/// # Safety
+///
+/// The layout of `Self` with metadata `len` must be the layout of an array of
+/// `len` `Element`s.
+pub unsafe trait SliceTail: SliceLikePointee {}
+
+// SAFETY: A slice is laid out as an array of its elements.
+unsafe impl<T> SliceTail for [T] {}
+
+// SAFETY: A `str` is laid out as an array of bytes.
+unsafe impl SliceTail for str {}
+
+/// A struct which consists of a sized header followed by a [`SliceTail`].
+///
+/// `#[derive(Pointee)]` implements this trait for `#[repr(C)]` and
+/// `#[repr(transparent)]` structs whose last field is a slice or `str`. The
+/// `Header` of a derived impl is the type of the only other field if there is
+/// one, and a tuple of the other fields' types in declaration order otherwise.
+///
+/// # Safety
+///
+/// - `layout_for(len)` must return the layout of `Self` with metadata `len` and
+///   the offset of the tail in bytes, or `None` if that layout overflows
+///   `isize`.
+/// - `write_header` must initialize every field of `Self` except for the tail.
+pub unsafe trait SliceDst: Pointee<Metadata = usize> {
+    /// The sized fields which precede the tail.
+    type Header;
+    /// The type of the last field.
+    type Tail: SliceTail + ?Sized;
+
+    /// Returns the layout of `Self` for a tail of the given length, along with
+    /// the offset of the tail.
+    fn layout_for(len: usize) -> Option<(Layout, usize)>;
+
+    /// Writes `header` to the fields of the value at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be valid for writes of the layout returned by
+    /// [`layout_for`](SliceDst::layout_for) and properly aligned.
+    unsafe fn write_header(ptr: *mut u8, header: Self::Header);
+}
+
+// SAFETY: A slice has no header and its tail starts at offset 0.
+unsafe impl<T> SliceDst for [T] {
+    type Header = ();
+    type Tail = [T];
+
+    #[inline]
+    fn layout_for(len: usize) -> Option<(Layout, usize)> {
+        Some((Layout::array::<T>(len).ok()?, 0))
+    }
+
+    #[inline]
+    unsafe fn write_header(_: *mut u8, _: Self::Header) {}
+}
+
+// SAFETY: A `str` has no header and its tail starts at offset 0.
+unsafe impl SliceDst for str {
+    type Header = ();
+    type Tail = str;
+
+    #[inline]
+    fn layout_for(len: usize) -> Option<(Layout, usize)> {
+        Some((Layout::array::<u8>(len).ok()?, 0))
+    }
+
+    #[inline]
+    unsafe fn write_header(_: *mut u8, _: Self::Header) {}
+}
+
+/// Builds boxed [`SliceDst`]s from a header and the elements of their tail.
+///
+/// The result can be any pointer type which can be created from a
+/// `Box<D>`, such as `Box<D>`, `Rc<D>`, or `Arc<D>`. Building an `Rc` or `Arc`
+/// moves the value out of a temporary box.
+///
+/// If an element fails to construct, any elements which were already written
+/// are dropped along with the header and the memory is freed.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use ptr_meta::{DstBuilder, Pointee};
+///
+/// #[derive(Pointee)]
+/// #[repr(C)]
+/// struct Block<H, T> {
+///     header: H,
+///     elements: [T],
+/// }
+///
+/// let block: Box<Block<&str, u32>> =
+///     DstBuilder::new("squares").build((1..5).map(|x| x * x));
+/// assert_eq!(block.header, "squares");
+/// assert_eq!(block.elements, [1, 4, 9, 16]);
+///
+/// let shared: Arc<Block<u8, String>> = DstBuilder::new(2)
+///     .build_from_slice(&["a".to_string(), "b".to_string()]);
+/// assert_eq!(shared.elements.len(), 2);
+///
+/// #[derive(Pointee)]
+/// #[repr(C)]
+/// struct Name {
+///     id: u32,
+///     name: str,
+/// }
+///
+/// let name: Box<Name> = DstBuilder::new(7).build_from_str("ptr_meta");
+/// assert_eq!(&name.name, "ptr_meta");
+/// ```
+#[cfg(feature = "alloc")]
+pub struct DstBuilder<D: SliceDst + ?Sized> {
+    header: D::Header,
+    _phantom: PhantomData<fn() -> Box<D>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<D: SliceDst + ValidateMetadata + ?Sized> DstBuilder<D> {
+    /// Returns a new builder with the given header.
+    #[inline]
+    pub fn new(header: D::Header) -> Self {
+        Self {
+            header,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Builds the value with the elements yielded by `elements` as its tail.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the iterator does not yield exactly as many elements as it
+    /// reports, if the length of the tail fails
+    /// [validation](ValidateMetadata), or if the layout of the value would
+    /// overflow `isize`.
+    pub fn build<P, I>(self, elements: I) -> P
+    where
+        D: SliceDst<Tail = [I::Item]>,
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+        P: From<Box<D>>,
+    {
+        self.try_build(elements)
+            .unwrap_or_else(|_| validation_failed())
+    }
+
+    /// Builds the value with the elements yielded by `elements` as its tail,
+    /// or returns an error if the length of the tail fails validation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the iterator does not yield exactly as many elements as it
+    /// reports, or if the layout of the value would overflow `isize`.
+    pub fn try_build<P, I>(self, elements: I) -> Result<P, D::Error>
+    where
+        D: SliceDst<Tail = [I::Item]>,
+        I: IntoIterator,
+        I::IntoIter: ExactSizeIterator,
+        P: From<Box<D>>,
+    {
+        let elements = elements.into_iter();
+        D::validate_metadata(elements.len())?;
+        // SAFETY: Any initialized elements form a valid slice.
+        Ok(P::from(unsafe { self.build_box(elements) }))
+    }
+
+    /// Builds the value with clones of the elements in `elements` as its tail.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of the tail fails validation, or if the layout of
+    /// the value would overflow `isize`.
+    pub fn build_from_slice<P, T>(self, elements: &[T]) -> P
+    where
+        D: SliceDst<Tail = [T]>,
+        T: Clone,
+        P: From<Box<D>>,
+    {
+        self.build(elements.iter().cloned())
+    }
+
+    /// Builds the value with clones of the elements in `elements` as its
+    /// tail, or returns an error if the length of the tail fails validation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layout of the value would overflow `isize`.
+    pub fn try_build_from_slice<P, T>(
+        self,
+        elements: &[T],
+    ) -> Result<P, D::Error>
+    where
+        D: SliceDst<Tail = [T]>,
+        T: Clone,
+        P: From<Box<D>>,
+    {
+        self.try_build(elements.iter().cloned())
+    }
+
+    /// Builds the value with a copy of `s` as its tail.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of the tail fails validation, or if the layout of
+    /// the value would overflow `isize`.
+    pub fn build_from_str<P>(self, s: &str) -> P
+    where
+        D: SliceDst<Tail = str>,
+        P: From<Box<D>>,
+    {
+        self.try_build_from_str(s)
+            .unwrap_or_else(|_| validation_failed())
+    }
+
+    /// Builds the value with a copy of `s` as its tail, or returns an error if
+    /// the length of the tail fails validation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layout of the value would overflow `isize`.
+    pub fn try_build_from_str<P>(self, s: &str) -> Result<P, D::Error>
+    where
+        D: SliceDst<Tail = str>,
+        P: From<Box<D>>,
+    {
+        D::validate_metadata(s.len())?;
+        // SAFETY: The bytes of a `str` are valid UTF-8.
+        Ok(P::from(unsafe { self.build_box(s.bytes()) }))
+    }
+
+    // SAFETY: The elements yielded by `elements` must form a valid tail.
+    unsafe fn build_box<I>(self, mut elements: I) -> Box<D>
+    where
+        I: ExactSizeIterator<Item = <D::Tail as SliceLikePointee>::Element>,
+    {
+        let len = elements.len();
+        let (layout, tail_offset) =
+            D::layout_for(len).expect("DST layout overflowed `isize`");
+
+        let ptr = if layout.size() == 0 {
+            ptr::null_mut::<u8>().wrapping_add(layout.align())
+        } else {
+            // SAFETY: `layout` has a non-zero size.
+            let ptr = unsafe { alloc(layout) };
+            if ptr.is_null() {
+                handle_alloc_error(layout);
+            }
+            ptr
+        };
+
+        // Drops the initialized elements and frees the memory if building
+        // the tail panics.
+        struct Guard<T> {
+            ptr: *mut u8,
+            layout: Layout,
+            elements: *mut T,
+            len: usize,
+        }
+
+        impl<T> Drop for Guard<T> {
+            fn drop(&mut self) {
+                // SAFETY: The first `len` elements have been initialized, and
+                // `ptr` was allocated with `layout` if it has a non-zero size.
+                unsafe {
+                    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                        self.elements,
+                        self.len,
+                    ));
+                    if self.layout.size() != 0 {
+                        dealloc(self.ptr, self.layout);
+                    }
+                }
+            }
+        }
+
+        let mut guard = Guard {
+            ptr,
+            layout,
+            elements: ptr
+                .wrapping_add(tail_offset)
+                .cast::<<D::Tail as SliceLikePointee>::Element>(),
+            len: 0,
+        };
+        while guard.len < len {
+            let element = elements.next().expect(
+                "iterator yielded fewer elements than its reported length",
+            );
+            // SAFETY: The tail has room for `len` elements and fewer than that
+            // have been written.
+            unsafe {
+                guard.elements.add(guard.len).write(element);
+            }
+            guard.len += 1;
+        }
+        assert!(
+            elements.next().is_none(),
+            "iterator yielded more elements than its reported length",
+        );
+        core::mem::forget(guard);
+
+        // SAFETY: `ptr` is valid for writes of `layout` and properly aligned.
+        unsafe {
+            D::write_header(ptr, self.header);
+        }
+        // SAFETY: Every field of the value has been initialized, and `ptr` was
+        // allocated by the global allocator with the layout of the value.
+        unsafe { Box::from_raw(from_raw_parts_mut(ptr.cast(), len)) }
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[cold]
+fn validation_failed() -> ! {
+    panic!("the length of the DST tail failed validation")
+}
+
+#[cfg(all(test, feature = "alloc", feature = "derive"))]
+mod tests {
+    use alloc::{boxed::Box, rc::Rc, string::String, sync::Arc, vec::Vec};
+
+    use super::DstBuilder;
+    use crate::Pointee;
+
+    #[derive(Pointee)]
+    #[ptr_meta(crate)]
+    #[repr(C)]
+    struct Block<H, T> {
+        header: H,
+        elements: [T],
+    }
+
+    #[derive(Pointee)]
+    #[ptr_meta(crate)]
+    #[repr(C)]
+    struct Labeled {
+        a: u8,
+        b: u64,
+        label: str,
+    }
+
+    #[test]
+    fn build_containers() {
+        let boxed: Box<Block<u16, u64>> = DstBuilder::new(3).build([1, 2, 3]);
+        assert_eq!(boxed.header, 3);
+        assert_eq!(boxed.elements, [1, 2, 3]);
+
+        let rc: Rc<Block<String, String>> = DstBuilder::new("h".into())
+            .build_from_slice(&["a".into(), "b".into()]);
+        assert_eq!(rc.header, "h");
+        assert_eq!(rc.elements, ["a", "b"]);
+
+        let arc: Arc<Labeled> = DstBuilder::new((1, 2)).build_from_str("hello");
+        assert_eq!((arc.a, arc.b), (1, 2));
+        assert_eq!(&arc.label, "hello");
+
+        let empty: Box<Block<(), ()>> = DstBuilder::new(()).build([]);
+        assert_eq!(empty.elements.len(), 0);
+
+        let slice: Box<[u8]> =
+            DstBuilder::<[u8]>::new(()).build(Vec::from([1, 2]));
+        assert_eq!(*slice, [1, 2]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn panic_drops_elements() {
+        use core::cell::Cell;
+
+        struct Counted<'a>(&'a Cell<usize>);
+
+        impl Drop for Counted<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Cell::new(0);
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                let _: Box<Block<Counted<'_>, Counted<'_>>> =
+                    DstBuilder::new(Counted(&drops)).build((0..4).map(|i| {
+                        if i == 2 {
+                            panic!("element construction failed");
+                        }
+                        Counted(&drops)
+                    }));
+            }));
+        assert!(result.is_err());
+        assert_eq!(drops.get(), 3);
+    }
+}
@@ -0,0 +1,303 @@
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    from_raw_parts_mut, to_raw_parts_mut, LayoutFromMetadata, Pointee,
+};
+
+/// A possibly-wide pointer which stores a tag in the low bits of its data
+/// address.
+///
+/// `TaggedPtr<T, BITS>` is the same size as `*mut T`. The lowest `BITS` bits of
+/// the data address are always zero for a properly-aligned pointer when the
+/// alignment of `T` is at least `2^BITS`, so they are used to store a tag. The
+/// pointer metadata is stored unmodified.
+///
+/// Tagging and untagging only offset the data address, so the pointer keeps
+/// its provenance and is compatible with strict provenance.
+///
+/// For pointees which implement [`LayoutFromMetadata`], whether they have
+/// enough alignment for `BITS` tag bits is checked:
+///
+/// - At compile time by [`new`](TaggedPtr::new), using
+///   [`LayoutFromMetadata::MIN_ALIGN`]. This is the exact alignment for `Sized`
+///   types and slices.
+/// - At runtime by [`try_new`](TaggedPtr::try_new), using the layout computed
+///   from the pointer's metadata. This works for trait objects, whose alignment
+///   is read from their vtable.
+///
+/// Pointers to any other pointee can be tagged with
+/// [`try_from_ptr`](TaggedPtr::try_from_ptr), which only checks the alignment
+/// of the pointer itself.
+///
+/// # Example
+///
+/// ```
+/// use core::any::Any;
+///
+/// use ptr_meta::TaggedPtr;
+///
+/// let mut values = [1u32, 2, 3];
+/// let tagged = TaggedPtr::<[u32], 2>::new(&mut values[..], 3);
+/// assert_eq!(tagged.tag(), 3);
+/// assert_eq!(unsafe { &*tagged.ptr() }, &[1, 2, 3]);
+///
+/// let mut value = 10u64;
+/// let tagged =
+///     TaggedPtr::<dyn Any, 3>::try_new(&mut value as &mut dyn Any, 5)
+///         .unwrap();
+/// assert_eq!(tagged.tag(), 5);
+/// assert_eq!(unsafe { &*tagged.ptr() }.downcast_ref::<u64>(), Some(&10));
+/// ```
+pub struct TaggedPtr<T: Pointee + ?Sized, const BITS: u32> {
+    ptr: *mut T,
+}
+
+impl<T: LayoutFromMetadata + ?Sized, const BITS: u32> TaggedPtr<T, BITS> {
+    /// Returns a new tagged pointer from a pointer and a tag.
+    ///
+    /// The alignment of `T` is checked against `BITS` at compile time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ptr` is not aligned to `2^BITS` or `tag` does not fit in
+    /// `BITS` bits.
+    #[inline]
+    pub fn new(ptr: *mut T, tag: usize) -> Self {
+        const {
+            assert!(
+                Self::TAG_MASK < T::MIN_ALIGN,
+                "the alignment of the pointee is too small to hold the tag",
+            );
+        }
+
+        Self::try_from_ptr(ptr, tag).expect(
+            "the pointer must be aligned and the tag must fit in `BITS` bits",
+        )
+    }
+
+    /// Returns a new tagged pointer from a pointer and a tag, or `None` if the
+    /// pointer cannot hold the tag.
+    ///
+    /// This checks the alignment of the pointee using the pointer's metadata,
+    /// so it can be used with trait objects. Returns `None` if the alignment
+    /// of the pointee is less than `2^BITS`, if `ptr` is not aligned to
+    /// `2^BITS`, or if `tag` does not fit in `BITS` bits.
+    #[inline]
+    pub fn try_new(ptr: *mut T, tag: usize) -> Option<Self> {
+        let (_, metadata) = to_raw_parts_mut(ptr);
+        let layout = T::layout_for_metadata(metadata)?;
+        if Self::TAG_MASK >= layout.align() {
+            return None;
+        }
+
+        Self::try_from_ptr(ptr, tag)
+    }
+}
+
+impl<T: Pointee + ?Sized, const BITS: u32> TaggedPtr<T, BITS> {
+    /// The mask of the bits of the data address which hold the tag.
+    pub const TAG_MASK: usize = {
+        assert!(BITS < usize::BITS, "too many tag bits");
+        (1 << BITS) - 1
+    };
+
+    /// Returns a new tagged pointer from a pointer and a tag, or `None` if the
+    /// pointer cannot hold the tag.
+    ///
+    /// Unlike [`try_new`](TaggedPtr::try_new), this doesn't check the
+    /// alignment of the pointee, so it can be used with any pointee. Returns
+    /// `None` if `ptr` is not aligned to `2^BITS`, or if `tag` does not fit in
+    /// `BITS` bits.
+    #[inline]
+    pub fn try_from_ptr(ptr: *mut T, tag: usize) -> Option<Self> {
+        let (data_address, metadata) = to_raw_parts_mut(ptr);
+        if addr(data_address) & Self::TAG_MASK != 0
+            || tag & !Self::TAG_MASK != 0
+        {
+            return None;
+        }
+
+        Some(Self {
+            ptr: from_raw_parts_mut(
+                data_address.cast::<u8>().wrapping_add(tag).cast(),
+                metadata,
+            ),
+        })
+    }
+
+    /// Returns the untagged pointer.
+    #[inline]
+    pub fn ptr(self) -> *mut T {
+        let (data_address, metadata) = to_raw_parts_mut(self.ptr);
+        from_raw_parts_mut(
+            data_address.cast::<u8>().wrapping_sub(self.tag()).cast(),
+            metadata,
+        )
+    }
+
+    /// Returns the tag.
+    #[inline]
+    pub fn tag(self) -> usize {
+        addr(self.ptr.cast()) & Self::TAG_MASK
+    }
+
+    /// Returns the pointer metadata.
+    #[inline]
+    pub fn metadata(self) -> T::Metadata {
+        crate::metadata(self.ptr)
+    }
+
+    /// Returns the same pointer with a different tag.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tag` does not fit in `BITS` bits.
+    #[inline]
+    pub fn with_tag(self, tag: usize) -> Self {
+        assert!(
+            tag & !Self::TAG_MASK == 0,
+            "the tag must fit in `BITS` bits"
+        );
+        Self::try_from_ptr(self.ptr(), tag).unwrap()
+    }
+
+    /// Sets the tag of the pointer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tag` does not fit in `BITS` bits.
+    #[inline]
+    pub fn set_tag(&mut self, tag: usize) {
+        *self = self.with_tag(tag);
+    }
+}
+
+// Manual impls needed to avoid `T: $Trait` bounds.
+
+impl<T: Pointee + ?Sized, const BITS: u32> Copy for TaggedPtr<T, BITS> {}
+
+impl<T: Pointee + ?Sized, const BITS: u32> Clone for TaggedPtr<T, BITS> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Pointee + ?Sized, const BITS: u32> Eq for TaggedPtr<T, BITS> {}
+
+impl<T: Pointee + ?Sized, const BITS: u32> PartialEq for TaggedPtr<T, BITS> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        to_raw_parts_mut(self.ptr) == to_raw_parts_mut(other.ptr)
+    }
+}
+
+impl<T: Pointee + ?Sized, const BITS: u32> Hash for TaggedPtr<T, BITS> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        to_raw_parts_mut(self.ptr).hash(state)
+    }
+}
+
+impl<T: Pointee + ?Sized, const BITS: u32> fmt::Debug for TaggedPtr<T, BITS>
+where
+    T::Metadata: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaggedPtr")
+            .field("ptr", &self.ptr().cast::<()>())
+            .field("metadata", &self.metadata())
+            .field("tag", &self.tag())
+            .finish()
+    }
+}
+
+// Returns the address of a pointer without exposing its provenance.
+#[cfg(ptr_meta_strict_provenance)]
+#[clippy::msrv = "1.84"]
+#[inline]
+pub(crate) fn addr(ptr: *mut ()) -> usize {
+    ptr.addr()
+}
+
+// Returns the address of a pointer without exposing its provenance.
+//
+// This is the same polyfill as `sptr` uses for `<*mut T>::addr`, which was
+// stabilized after the MSRV.
+#[cfg(not(ptr_meta_strict_provenance))]
+#[allow(clippy::transmutes_expressible_as_ptr_casts)]
+#[inline]
+pub(crate) fn addr(ptr: *mut ()) -> usize {
+    // SAFETY: Thin pointers have the same layout as `usize`, and transmuting
+    // one to `usize` discards its provenance like `addr` does.
+    unsafe { core::mem::transmute::<*mut (), usize>(ptr) }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{any::Any, mem::size_of, ptr};
+
+    use super::TaggedPtr;
+    use crate::metadata;
+
+    #[test]
+    fn sized_and_slices() {
+        let mut value = 42u64;
+        let mut tagged = TaggedPtr::<u64, 3>::new(&mut value, 7);
+        assert_eq!(tagged.tag(), 7);
+        assert!(ptr::eq(tagged.ptr(), &value));
+
+        tagged.set_tag(2);
+        assert_eq!(tagged.tag(), 2);
+        // SAFETY: The untagged pointer points to `value`.
+        unsafe {
+            *tagged.ptr() += 1;
+        }
+        assert_eq!(value, 43);
+
+        let mut slice = [1u16, 2, 3];
+        let tagged = TaggedPtr::<[u16], 1>::new(&mut slice[..], 1);
+        assert_eq!(tagged.metadata(), 3);
+        assert_eq!(size_of::<TaggedPtr<[u16], 1>>(), size_of::<*mut [u16]>());
+        // SAFETY: The untagged pointer points to `slice`.
+        assert_eq!(unsafe { &*tagged.ptr() }, &[1, 2, 3]);
+
+        let untagged =
+            TaggedPtr::<str, 0>::new("hi" as *const str as *mut str, 0);
+        assert_eq!(untagged.tag(), 0);
+    }
+
+    #[test]
+    fn trait_objects() {
+        let mut value = 5u32;
+        let ptr = &mut value as &mut dyn Any as *mut dyn Any;
+
+        let tagged = TaggedPtr::<dyn Any, 2>::try_new(ptr, 3).unwrap();
+        assert_eq!(tagged.tag(), 3);
+        assert_eq!(tagged.metadata(), metadata(ptr));
+        // SAFETY: The untagged pointer points to `value`.
+        let any = unsafe { &*tagged.ptr() };
+        assert_eq!(any.downcast_ref::<u32>(), Some(&5));
+
+        assert!(TaggedPtr::<dyn Any, 3>::try_new(ptr, 0).is_none());
+        assert!(TaggedPtr::<dyn Any, 2>::try_new(ptr, 4).is_none());
+
+        let tagged = TaggedPtr::<dyn Any, 2>::try_from_ptr(ptr, 1).unwrap();
+        assert_eq!(tagged.tag(), 1);
+        assert!(ptr::eq(tagged.ptr(), ptr));
+    }
+
+    #[test]
+    #[should_panic]
+    fn misaligned() {
+        let bytes = [0u8; 8];
+        let ptr = bytes.as_ptr().wrapping_add(1) as *mut u8;
+        TaggedPtr::<[u16], 1>::new(
+            ptr::slice_from_raw_parts_mut(ptr.cast(), 1),
+            0,
+        );
+    }
+}
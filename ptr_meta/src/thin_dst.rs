@@ -0,0 +1,113 @@
+use crate::{from_raw_parts, from_raw_parts_mut, Pointee};
+
+/// A dynamically-sized type which stores its own length.
+///
+/// Like a C struct with a flexible array member, a `ThinDst` keeps the length
+/// of its tail in one of its header fields. A wide pointer to it can be
+/// rebuilt from a thin pointer by reading that field, so it can be received
+/// from C or a memory map, or stored behind a one-word pointer.
+///
+/// `#[derive(Pointee)]` implements `ThinDst` for structs whose last field is
+/// a slice or `str` with the `#[ptr_meta(len_field = ...)]` attribute. The
+/// length field must be a [`CompactLen`](crate::CompactLen).
+///
+/// # Safety
+///
+/// `read_len` must return the metadata of the value at `ptr`.
+///
+/// # Example
+///
+/// ```
+/// use ptr_meta::{Pointee, ThinDst};
+///
+/// #[derive(Pointee)]
+/// #[ptr_meta(len_field = len)]
+/// #[repr(C)]
+/// struct Message {
+///     len: u16,
+///     bytes: [u8],
+/// }
+///
+/// #[repr(C, align(2))]
+/// struct Buffer([u8; 6]);
+///
+/// let buffer = Buffer([3, 0, b'a', b'b', b'c', 0]);
+/// let thin = buffer.0.as_ptr().cast::<()>();
+/// let message = unsafe { &*Message::from_thin(thin) };
+/// assert_eq!(&message.bytes, b"abc");
+/// ```
+pub unsafe trait ThinDst: Pointee<Metadata = usize> {
+    /// Reads the length stored in the value at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be aligned for `Self` and point to a value of `Self` whose
+    /// length field is initialized.
+    unsafe fn read_len(ptr: *const ()) -> usize;
+
+    /// Returns a wide pointer to the value at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be aligned for `Self` and point to a value of `Self` whose
+    /// length field is initialized.
+    #[inline]
+    unsafe fn from_thin(ptr: *const ()) -> *const Self {
+        // SAFETY: The caller has upheld the safety requirements of
+        // `read_len`.
+        from_raw_parts(ptr, unsafe { Self::read_len(ptr) })
+    }
+
+    /// Returns a wide mutable pointer to the value at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be aligned for `Self` and point to a value of `Self` whose
+    /// length field is initialized.
+    #[inline]
+    unsafe fn from_thin_mut(ptr: *mut ()) -> *mut Self {
+        // SAFETY: The caller has upheld the safety requirements of
+        // `read_len`.
+        from_raw_parts_mut(ptr, unsafe { Self::read_len(ptr) })
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::ThinDst;
+    use crate::Pointee;
+
+    #[derive(Pointee)]
+    #[ptr_meta(crate, len_field = count)]
+    #[repr(C)]
+    struct Samples {
+        rate: u16,
+        count: u32,
+        values: [i16],
+    }
+
+    #[test]
+    fn from_thin() {
+        let mut words = [0u32; 4];
+        words[0] = 44_100;
+        words[1] = 3;
+        // SAFETY: `words` is large enough and aligned for `Samples` with
+        // three values.
+        unsafe {
+            let values = words.as_mut_ptr().add(2).cast::<i16>();
+            values.write(-1);
+            values.add(1).write(0);
+            values.add(2).write(1);
+        }
+
+        let thin = words.as_mut_ptr().cast::<()>();
+        // SAFETY: `thin` points to an initialized `Samples`.
+        let samples = unsafe { &mut *Samples::from_thin_mut(thin) };
+        assert_eq!(crate::metadata(samples), 3);
+        assert_eq!(&samples.values, &[-1, 0, 1]);
+        samples.values[0] = 5;
+        // SAFETY: `thin` points to an initialized `Samples`.
+        let samples = unsafe { &*Samples::from_thin(thin) };
+        assert_eq!(samples.values[0], 5);
+    }
+}
@@ -0,0 +1,199 @@
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, rc::Rc};
+
+use crate::{from_raw_parts, from_raw_parts_mut, metadata, Pointee};
+
+/// A type which is a transparent wrapper around another, possibly unsized,
+/// type.
+///
+/// References and smart pointers to the `Inner` type can be safely converted
+/// to and from references and smart pointers to `Self`.
+///
+/// `#[derive(Pointee)]` implements `Transparent` for `#[repr(transparent)]`
+/// structs with the `#[ptr_meta(transparent_casts)]` attribute. The `Inner`
+/// type of a derived impl is the type of the last field.
+///
+/// # Safety
+///
+/// `Self` must have the same layout and validity as `Inner`, and pointers to
+/// `Self` and `Inner` with the same metadata must describe the same memory.
+///
+/// # Example
+///
+/// ```
+/// use ptr_meta::{Pointee, Transparent};
+///
+/// #[derive(Pointee)]
+/// #[ptr_meta(transparent_casts)]
+/// #[repr(transparent)]
+/// struct Name {
+///     inner: str,
+/// }
+///
+/// let name = Name::from_ref("Ferris");
+/// assert_eq!(name.as_inner(), "Ferris");
+///
+/// let boxed: Box<Name> = Name::from_box("Corro".into());
+/// assert_eq!(&*Name::into_inner_box(boxed), "Corro");
+/// ```
+pub unsafe trait Transparent: Pointee {
+    /// The type which `Self` wraps.
+    type Inner: Pointee<Metadata = Self::Metadata> + ?Sized;
+
+    /// Converts a reference to the inner type into a reference to `Self`.
+    #[inline]
+    fn from_ref(inner: &Self::Inner) -> &Self {
+        let ptr = from_raw_parts::<Self>(
+            (inner as *const Self::Inner).cast(),
+            metadata(inner),
+        );
+        // SAFETY: `Self` has the same layout and validity as `Self::Inner`, so
+        // `ptr` points to a valid `Self` for the lifetime of `inner`.
+        unsafe { &*ptr }
+    }
+
+    /// Converts a mutable reference to the inner type into a mutable
+    /// reference to `Self`.
+    #[inline]
+    fn from_mut(inner: &mut Self::Inner) -> &mut Self {
+        let ptr = from_raw_parts_mut::<Self>(
+            (inner as *mut Self::Inner).cast(),
+            metadata(inner),
+        );
+        // SAFETY: `Self` has the same layout and validity as `Self::Inner`, so
+        // `ptr` points to a valid `Self` which is mutably borrowed for the
+        // lifetime of `inner`.
+        unsafe { &mut *ptr }
+    }
+
+    /// Returns a reference to the inner value.
+    #[inline]
+    fn as_inner(&self) -> &Self::Inner {
+        let ptr = from_raw_parts::<Self::Inner>(
+            (self as *const Self).cast(),
+            metadata(self),
+        );
+        // SAFETY: `Self` has the same layout and validity as `Self::Inner`.
+        unsafe { &*ptr }
+    }
+
+    /// Returns a mutable reference to the inner value.
+    #[inline]
+    fn as_inner_mut(&mut self) -> &mut Self::Inner {
+        let ptr = from_raw_parts_mut::<Self::Inner>(
+            (self as *mut Self).cast(),
+            metadata(self),
+        );
+        // SAFETY: `Self` has the same layout and validity as `Self::Inner`.
+        unsafe { &mut *ptr }
+    }
+
+    /// Converts a box of the inner type into a box of `Self`.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn from_box(inner: Box<Self::Inner>) -> Box<Self> {
+        let ptr = Box::into_raw(inner);
+        // SAFETY: `ptr` was allocated by a `Box` with the layout of the inner
+        // value, which is the same as the layout of `Self`.
+        unsafe { Box::from_raw(from_raw_parts_mut(ptr.cast(), metadata(ptr))) }
+    }
+
+    /// Converts a box of `Self` into a box of the inner type.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn into_inner_box(this: Box<Self>) -> Box<Self::Inner> {
+        let ptr = Box::into_raw(this);
+        // SAFETY: `ptr` was allocated by a `Box` with the layout of `Self`,
+        // which is the same as the layout of the inner value.
+        unsafe { Box::from_raw(from_raw_parts_mut(ptr.cast(), metadata(ptr))) }
+    }
+
+    /// Converts an `Rc` of the inner type into an `Rc` of `Self`.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn from_rc(inner: Rc<Self::Inner>) -> Rc<Self> {
+        let ptr = Rc::into_raw(inner);
+        // SAFETY: `ptr` was returned by `Rc::into_raw` for a value with the
+        // same layout as `Self`.
+        unsafe { Rc::from_raw(from_raw_parts(ptr.cast(), metadata(ptr))) }
+    }
+
+    /// Converts an `Rc` of `Self` into an `Rc` of the inner type.
+    #[cfg(feature = "alloc")]
+    #[inline]
+    fn into_inner_rc(this: Rc<Self>) -> Rc<Self::Inner> {
+        let ptr = Rc::into_raw(this);
+        // SAFETY: `ptr` was returned by `Rc::into_raw` for a value with the
+        // same layout as the inner value.
+        unsafe { Rc::from_raw(from_raw_parts(ptr.cast(), metadata(ptr))) }
+    }
+
+    /// Converts an `Arc` of the inner type into an `Arc` of `Self`.
+    #[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+    #[inline]
+    fn from_arc(inner: Arc<Self::Inner>) -> Arc<Self> {
+        let ptr = Arc::into_raw(inner);
+        // SAFETY: `ptr` was returned by `Arc::into_raw` for a value with the
+        // same layout as `Self`.
+        unsafe { Arc::from_raw(from_raw_parts(ptr.cast(), metadata(ptr))) }
+    }
+
+    /// Converts an `Arc` of `Self` into an `Arc` of the inner type.
+    #[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+    #[inline]
+    fn into_inner_arc(this: Arc<Self>) -> Arc<Self::Inner> {
+        let ptr = Arc::into_raw(this);
+        // SAFETY: `ptr` was returned by `Arc::into_raw` for a value with the
+        // same layout as the inner value.
+        unsafe { Arc::from_raw(from_raw_parts(ptr.cast(), metadata(ptr))) }
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use core::marker::PhantomData;
+
+    use super::Transparent;
+    use crate::Pointee;
+
+    #[derive(Pointee)]
+    #[ptr_meta(crate, transparent_casts)]
+    #[repr(transparent)]
+    struct Wrapper<T> {
+        _marker: PhantomData<fn() -> T>,
+        inner: [T],
+    }
+
+    #[test]
+    fn references() {
+        let mut values = [1u32, 2, 3];
+        let wrapper = Wrapper::from_mut(&mut values[..]);
+        wrapper.as_inner_mut()[0] = 10;
+        assert_eq!(Wrapper::from_ref(&values[..]).as_inner(), &[10, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn smart_pointers() {
+        use alloc::{rc::Rc, vec};
+
+        let boxed = Wrapper::from_box(vec![1u8, 2].into_boxed_slice());
+        assert_eq!(&*Wrapper::into_inner_box(boxed), &[1, 2]);
+
+        let rc = Wrapper::from_rc(Rc::<[u8]>::from([3, 4]));
+        let clone = rc.clone();
+        let inner = Wrapper::into_inner_rc(rc);
+        assert_eq!(&*inner, &[3, 4]);
+        assert_eq!(Rc::strong_count(&clone), 2);
+
+        #[cfg(target_has_atomic = "ptr")]
+        {
+            use alloc::sync::Arc;
+
+            let arc = Wrapper::from_arc(Arc::<[u8]>::from([5]));
+            assert_eq!(&*Wrapper::into_inner_arc(arc), &[5]);
+        }
+    }
+}
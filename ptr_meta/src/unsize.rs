@@ -0,0 +1,250 @@
+use core::{any::Any, error::Error, ptr};
+
+use crate::{from_raw_parts, from_raw_parts_mut, metadata, Pointee};
+
+/// A type which a pointer to a `T` can be unsized into.
+///
+/// This is the reverse of [`Unsize`], in the same way that [`From`] is the
+/// reverse of [`Into`]. Every `UnsizeFrom` implementation provides the
+/// corresponding `Unsize` implementation, and coherence rules allow
+/// `UnsizeFrom` to be implemented for local trait objects from any source type.
+///
+/// This is implemented for slices from arrays, and for `dyn Any` and
+/// `dyn Error` (optionally with `+ Send` and/or `+ Sync`) from the types which
+/// implement them. `#[ptr_meta::pointee]` implements it for the trait objects
+/// it generates `Pointee` implementations for.
+///
+/// # Safety
+///
+/// `unsize_metadata` must return metadata which, combined with the data
+/// address of any valid `T`, makes a valid pointer to `Self`.
+pub unsafe trait UnsizeFrom<T>: Pointee {
+    /// Returns the metadata for a pointer to a `T` unsized into a pointer to
+    /// `Self`.
+    fn unsize_metadata() -> Self::Metadata;
+}
+
+/// A type which can be unsized into a `U`.
+///
+/// `CoerceUnsized` and the compiler's `Unsize` are unstable, so smart
+/// pointers defined outside of the standard library can't be coerced from
+/// `Ptr<T>` into `Ptr<dyn Trait>`. `Unsize` provides the metadata for that
+/// conversion instead, and [`coerce_ptr`] and [`coerce_ptr_mut`] perform it
+/// on raw pointers.
+///
+/// `Unsize` is implemented for every type `T` and `U` where `U` implements
+/// [`UnsizeFrom<T>`].
+///
+/// # Safety
+///
+/// `unsize_metadata` must return metadata which, combined with the data
+/// address of any valid `Self`, makes a valid pointer to `U`.
+///
+/// # Example
+///
+/// ```
+/// use ptr_meta::{coerce_ptr, Unsize};
+///
+/// #[ptr_meta::pointee]
+/// trait Shape {
+///     fn area(&self) -> f32;
+/// }
+///
+/// struct Square(f32);
+///
+/// impl Shape for Square {
+///     fn area(&self) -> f32 {
+///         self.0 * self.0
+///     }
+/// }
+///
+/// struct Handle<T: ?Sized>(Box<T>);
+///
+/// impl<T> Handle<T> {
+///     fn unsize<U: ?Sized>(self) -> Handle<U>
+///     where
+///         T: Unsize<U>,
+///         U: ptr_meta::Pointee,
+///     {
+///         let ptr = coerce_ptr(Box::into_raw(self.0));
+///         Handle(unsafe { Box::from_raw(ptr.cast_mut()) })
+///     }
+/// }
+///
+/// let shape: Handle<dyn Shape> = Handle(Box::new(Square(2.0))).unsize();
+/// assert_eq!(shape.0.area(), 4.0);
+///
+/// let slice: Handle<[u8]> = Handle(Box::new([1u8, 2, 3])).unsize();
+/// assert_eq!(*slice.0, [1, 2, 3]);
+/// ```
+pub unsafe trait Unsize<U: Pointee + ?Sized>: Sized {
+    /// Returns the metadata for a pointer to `Self` unsized into a pointer to
+    /// `U`.
+    fn unsize_metadata() -> U::Metadata;
+}
+
+// SAFETY: `UnsizeFrom` has the same safety requirements as `Unsize`.
+unsafe impl<T, U: UnsizeFrom<T> + ?Sized> Unsize<U> for T {
+    #[inline]
+    fn unsize_metadata() -> U::Metadata {
+        U::unsize_metadata()
+    }
+}
+
+/// Unsizes a pointer to a `T` into a pointer to a `U`.
+///
+/// The returned pointer has the same data address as `ptr`.
+#[inline]
+pub fn coerce_ptr<T: Unsize<U>, U: Pointee + ?Sized>(
+    ptr: *const T,
+) -> *const U {
+    from_raw_parts(ptr.cast(), T::unsize_metadata())
+}
+
+/// Unsizes a mutable pointer to a `T` into a mutable pointer to a `U`.
+///
+/// The returned pointer has the same data address as `ptr`.
+#[inline]
+pub fn coerce_ptr_mut<T: Unsize<U>, U: Pointee + ?Sized>(
+    ptr: *mut T,
+) -> *mut U {
+    from_raw_parts_mut(ptr.cast(), T::unsize_metadata())
+}
+
+/// Returns the vtable of `T` for the trait object `Dyn`.
+///
+/// This is used by `#[ptr_meta::pointee]` to implement `UnsizeFrom`.
+#[doc(hidden)]
+#[inline]
+pub fn vtable_for<T, Dyn: Pointee + ?Sized>(
+    coerce: fn(*const T) -> *const Dyn,
+) -> Dyn::Metadata {
+    metadata(coerce(ptr::null()))
+}
+
+// SAFETY: An array can be viewed as a slice of its elements with length `N`.
+unsafe impl<T, const N: usize> UnsizeFrom<[T; N]> for [T] {
+    #[inline]
+    fn unsize_metadata() -> usize {
+        N
+    }
+}
+
+macro_rules! impl_unsize_from_dyn {
+    ($($ty:ty => ($($bound:tt)*)),* $(,)?) => {
+        $(
+            // SAFETY: The metadata is the vtable of `T` for the trait object,
+            // obtained by the compiler's own unsizing coercion.
+            unsafe impl<T: $($bound)*> UnsizeFrom<T> for $ty {
+                #[inline]
+                fn unsize_metadata() -> Self::Metadata {
+                    vtable_for::<T, Self>(|ptr| ptr)
+                }
+            }
+        )*
+    };
+}
+
+impl_unsize_from_dyn! {
+    dyn Any => (Any),
+    dyn Any + Send => (Any + Send),
+    dyn Any + Sync => (Any + Sync),
+    dyn Any + Send + Sync => (Any + Send + Sync),
+    dyn Error => (Error + 'static),
+    dyn Error + Send => (Error + Send + 'static),
+    dyn Error + Sync => (Error + Sync + 'static),
+    dyn Error + Send + Sync => (Error + Send + Sync + 'static),
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{any::Any, error::Error, fmt, ptr};
+
+    use super::{coerce_ptr, coerce_ptr_mut, Unsize};
+    use crate::{metadata, DynMetadata};
+
+    // Vtables may be duplicated, so compare the layouts they describe.
+    fn assert_same_vtable<T, U>(expected: *const U)
+    where
+        T: Unsize<U>,
+        U: crate::Pointee<Metadata = DynMetadata<U>> + ?Sized,
+    {
+        assert_eq!(T::unsize_metadata().layout(), metadata(expected).layout());
+    }
+
+    #[test]
+    fn arrays() {
+        let mut array = [1u32, 2, 3];
+        let slice = coerce_ptr::<_, [u32]>(&array);
+        assert!(ptr::eq(slice, &array[..]));
+
+        let slice = coerce_ptr_mut::<_, [u32]>(&mut array);
+        // SAFETY: `slice` points to `array`.
+        unsafe {
+            (*slice)[2] = 30;
+        }
+        assert_eq!(array, [1, 2, 30]);
+    }
+
+    #[test]
+    fn builtin_trait_objects() {
+        #[derive(Debug)]
+        struct Oops;
+
+        impl fmt::Display for Oops {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "oops")
+            }
+        }
+
+        impl Error for Oops {}
+
+        let value = 5u16;
+        let any = coerce_ptr::<_, dyn Any + Send + Sync>(&value);
+        // SAFETY: `any` points to `value`.
+        assert_eq!(unsafe { &*any }.downcast_ref::<u16>(), Some(&5));
+        assert_same_vtable::<u16, dyn Any>(&value as &dyn Any);
+
+        let error = coerce_ptr::<_, dyn Error + Send>(&Oops);
+        // SAFETY: `error` points to a valid `Oops`.
+        assert!(unsafe { &*error }.source().is_none());
+        assert_same_vtable::<Oops, dyn Error + Sync>(
+            &Oops as &(dyn Error + Sync),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn pointee_traits() {
+        #[crate::pointee(crate)]
+        trait Named {
+            fn name(&self) -> &'static str;
+        }
+
+        impl Named for u8 {
+            fn name(&self) -> &'static str {
+                "u8"
+            }
+        }
+
+        #[crate::pointee(crate)]
+        trait Generic<T> {
+            fn get(&self) -> T;
+        }
+
+        impl Generic<u32> for &str {
+            fn get(&self) -> u32 {
+                self.len() as u32
+            }
+        }
+
+        let named = coerce_ptr::<_, dyn Named>(&1u8);
+        // SAFETY: `named` points to a valid `u8`.
+        assert_eq!(unsafe { &*named }.name(), "u8");
+
+        let text = "four";
+        let generic = coerce_ptr::<_, dyn Generic<u32>>(&text);
+        // SAFETY: `generic` points to `text`.
+        assert_eq!(unsafe { &*generic }.get(), 4);
+    }
+}
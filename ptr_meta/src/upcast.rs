@@ -0,0 +1,157 @@
+use crate::{DynMetadata, DynPointee};
+
+/// A trait object type which can be upcast to the trait object of one of its
+/// supertraits.
+///
+/// `#[ptr_meta::pointee(upcast(...))]` implements this and [`UpcastMetadata`]
+/// for the trait objects it generates `Pointee` implementations for, with each
+/// of the listed supertraits.
+///
+/// On Rust 1.86 and later, this uses native trait upcasting. On earlier
+/// compilers, the trait gets a hidden supertrait which records the vtable of
+/// each listed supertrait for the implementing type. That supertrait is
+/// implemented for all sized types which implement the listed supertraits, so
+/// the trait can only be implemented for sized types on those compilers.
+///
+/// # Safety
+///
+/// `upcast_ref` and `upcast_mut` must return references to the same value as
+/// `self`.
+///
+/// # Example
+///
+/// ```
+/// use core::{any::Any, fmt::Debug};
+///
+/// use ptr_meta::Upcast;
+///
+/// #[ptr_meta::pointee(upcast(Debug, Any))]
+/// trait Shape: Debug + Any {}
+///
+/// #[derive(Debug)]
+/// struct Square;
+///
+/// impl Shape for Square {}
+///
+/// let shape: &dyn Shape = &Square;
+/// let debug: &dyn Debug = shape.upcast_ref();
+/// assert_eq!(format!("{debug:?}"), "Square");
+/// let any: &dyn Any = shape.upcast_ref();
+/// assert!(any.is::<Square>());
+/// ```
+pub unsafe trait Upcast<Super: ?Sized>: DynPointee {
+    /// Upcasts a reference to `Self` into a reference to `Super`.
+    fn upcast_ref(&self) -> &Super;
+
+    /// Upcasts a mutable reference to `Self` into a mutable reference to
+    /// `Super`.
+    fn upcast_mut(&mut self) -> &mut Super;
+}
+
+/// A trait object type whose metadata can be upcast to the metadata of one of
+/// its supertraits.
+///
+/// Unlike [`Upcast`], this doesn't need a value to upcast. Getting supertrait
+/// metadata without a value requires native trait upcasting, so
+/// `#[ptr_meta::pointee(upcast(...))]` only implements this on Rust 1.86 and
+/// later.
+///
+/// # Safety
+///
+/// `upcast_metadata` must return the metadata of `Super` for the same concrete
+/// type as `metadata`, and `upcast_ptr` and `upcast_ptr_mut` must return
+/// pointers with the same data address as `ptr` and upcast metadata.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(not(ptr_meta_trait_upcasting))]
+/// # fn main() {}
+/// # #[cfg(ptr_meta_trait_upcasting)]
+/// # fn main() {
+/// use core::{any::Any, fmt::Debug};
+///
+/// use ptr_meta::{metadata, UpcastMetadata};
+///
+/// #[ptr_meta::pointee(upcast(Debug, Any))]
+/// trait Shape: Debug + Any {}
+///
+/// #[derive(Debug)]
+/// struct Square;
+///
+/// impl Shape for Square {}
+///
+/// let shape: &dyn Shape = &Square;
+/// let debug = <dyn Shape as UpcastMetadata<dyn Debug>>::upcast_metadata(
+///     metadata(shape),
+/// );
+/// assert_eq!(debug.size_of(), 0);
+/// let any: *const dyn Any = <dyn Shape>::upcast_ptr(shape);
+/// assert!(unsafe { &*any }.is::<Square>());
+/// # }
+/// ```
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` does not implement `UpcastMetadata<{Super}>`",
+    note = "`#[ptr_meta::pointee(upcast(...))]` only implements \
+            `UpcastMetadata` on Rust 1.86 and later, which have native trait \
+            upcasting",
+    note = "use `Upcast` to upcast references on earlier compilers"
+)]
+pub unsafe trait UpcastMetadata<Super: ?Sized>: Upcast<Super> {
+    /// Returns the metadata of `Super` for the concrete type described by
+    /// `metadata`.
+    fn upcast_metadata(metadata: DynMetadata<Self>) -> DynMetadata<Super>;
+
+    /// Upcasts a pointer to `Self` into a pointer to `Super`.
+    ///
+    /// The pointer does not need to be valid.
+    fn upcast_ptr(ptr: *const Self) -> *const Super;
+
+    /// Upcasts a mutable pointer to `Self` into a mutable pointer to `Super`.
+    ///
+    /// The pointer does not need to be valid.
+    fn upcast_ptr_mut(ptr: *mut Self) -> *mut Super;
+}
+
+#[repr(C)]
+struct DynComponents<Dyn: ?Sized> {
+    data_address: *const (),
+    metadata: DynMetadata<Dyn>,
+}
+
+/// Returns the metadata of a pointer to a trait object.
+///
+/// This is used by `#[ptr_meta::pointee(upcast(...))]` to get the metadata of
+/// supertrait objects which don't implement `Pointee`.
+///
+/// # Safety
+///
+/// `Dyn` must be a trait object type.
+#[doc(hidden)]
+#[inline]
+pub unsafe fn dyn_metadata<Dyn: ?Sized>(ptr: *const Dyn) -> DynMetadata<Dyn> {
+    // SAFETY: The caller has guaranteed that `Dyn` is a trait object type, so
+    // `*const Dyn` is a data address followed by a vtable pointer.
+    unsafe { core::mem::transmute_copy::<_, DynComponents<Dyn>>(&ptr) }.metadata
+}
+
+/// Casts a pointer to a trait object into a pointer to the same trait object
+/// with different auto traits.
+///
+/// This is used by `#[ptr_meta::pointee(upcast(...))]` to add auto traits to
+/// supertrait objects without native trait upcasting.
+///
+/// # Safety
+///
+/// `From` and `To` must be trait objects of the same trait with different
+/// auto traits, and the value `ptr` points to must implement those auto
+/// traits.
+#[doc(hidden)]
+#[inline]
+pub unsafe fn cast_dyn<From: ?Sized, To: ?Sized>(
+    ptr: *const From,
+) -> *const To {
+    // SAFETY: The caller has guaranteed that `From` and `To` are trait objects
+    // of the same trait, which have the same vtables.
+    unsafe { core::mem::transmute_copy(&ptr) }
+}
@@ -0,0 +1,199 @@
+use core::{any::Any, convert::Infallible, error::Error, ffi::CStr};
+
+use crate::{from_raw_parts, from_raw_parts_mut, Pointee};
+
+/// A type whose pointer metadata may need to be validated.
+///
+/// Checked APIs like [`try_from_raw_parts`], [`DstReader`](crate::DstReader),
+/// and the `try_` methods of `DstBuilder` call `validate_metadata` before
+/// creating a pointer. Unchecked APIs like [`from_raw_parts`] never do.
+///
+/// This is implemented with an [`Infallible`] error for every type provided by
+/// this crate. `#[derive(Pointee)]` implements it for every struct, calling
+/// the function given by `#[ptr_meta(validate = ...)]` if there is one and
+/// validating the metadata as the last field otherwise. `#[ptr_meta::pointee]`
+/// implements it for the trait objects it generates `Pointee` implementations
+/// for.
+///
+/// # Example
+///
+/// ```
+/// use ptr_meta::{try_from_raw_parts, Pointee};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct NotPowerOfTwo;
+///
+/// fn power_of_two(len: usize) -> Result<(), NotPowerOfTwo> {
+///     len.is_power_of_two().then_some(()).ok_or(NotPowerOfTwo)
+/// }
+///
+/// #[derive(Pointee)]
+/// #[ptr_meta(validate = power_of_two, validate_error = NotPowerOfTwo)]
+/// #[repr(C)]
+/// struct Table {
+///     entries: [u32],
+/// }
+///
+/// let entries = [0u32; 4];
+/// let address = entries.as_ptr().cast();
+/// assert!(try_from_raw_parts::<Table>(address, 4).is_ok());
+/// assert_eq!(try_from_raw_parts::<Table>(address, 3), Err(NotPowerOfTwo));
+/// ```
+pub trait ValidateMetadata: Pointee {
+    /// The error returned when the metadata is invalid.
+    type Error;
+
+    /// Returns whether `metadata` is valid metadata for `Self`.
+    fn validate_metadata(metadata: Self::Metadata) -> Result<(), Self::Error>;
+}
+
+/// Returns a raw pointer with the given data address and metadata if the
+/// metadata is valid.
+///
+/// This is the checked version of [`from_raw_parts`].
+#[inline]
+pub fn try_from_raw_parts<T: ValidateMetadata + ?Sized>(
+    data_address: *const (),
+    metadata: T::Metadata,
+) -> Result<*const T, T::Error> {
+    T::validate_metadata(metadata)?;
+    Ok(from_raw_parts(data_address, metadata))
+}
+
+/// Returns a mutable raw pointer with the given data address and metadata if
+/// the metadata is valid.
+///
+/// This is the checked version of [`from_raw_parts_mut`].
+#[inline]
+pub fn try_from_raw_parts_mut<T: ValidateMetadata + ?Sized>(
+    data_address: *mut (),
+    metadata: T::Metadata,
+) -> Result<*mut T, T::Error> {
+    T::validate_metadata(metadata)?;
+    Ok(from_raw_parts_mut(data_address, metadata))
+}
+
+macro_rules! impl_validate_infallible {
+    ($($(#[$attr:meta])* [$($params:tt)*] $ty:ty),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            impl<$($params)*> ValidateMetadata for $ty {
+                type Error = Infallible;
+
+                #[inline]
+                fn validate_metadata(
+                    _: Self::Metadata,
+                ) -> Result<(), Self::Error> {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_validate_infallible! {
+    [T] T,
+    [T] [T],
+    [] str,
+    [] CStr,
+    #[cfg(feature = "std")]
+    [] std::ffi::OsStr,
+    [] dyn Any,
+    [] dyn Any + Send,
+    [] dyn Any + Sync,
+    [] dyn Any + Send + Sync,
+    [] dyn Error,
+    [] dyn Error + Send,
+    [] dyn Error + Sync,
+    [] dyn Error + Send + Sync,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use super::{try_from_raw_parts, try_from_raw_parts_mut};
+
+    #[test]
+    fn infallible() {
+        let mut values = [1u8, 2, 3];
+        let ptr = try_from_raw_parts::<[u8]>(values.as_ptr().cast(), 3);
+        assert_eq!(ptr.map(|ptr| ptr.len()), Ok::<_, Infallible>(3));
+        let ptr = try_from_raw_parts_mut::<str>(values.as_mut_ptr().cast(), 2);
+        assert!(ptr.is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "derive")]
+    fn derived() {
+        use crate::{DstReader, Pointee, RecordError};
+
+        #[derive(Debug, PartialEq)]
+        struct Empty;
+
+        fn non_empty(len: usize) -> Result<(), Empty> {
+            if len == 0 {
+                Err(Empty)
+            } else {
+                Ok(())
+            }
+        }
+
+        #[derive(Pointee)]
+        #[ptr_meta(crate, validate = non_empty, validate_error = Empty)]
+        #[repr(C)]
+        struct Word {
+            letters: [u8],
+        }
+
+        #[derive(Pointee)]
+        #[ptr_meta(crate)]
+        #[repr(C)]
+        struct Unchecked {
+            letters: [u8],
+        }
+
+        let letters = b"hi";
+        let address = letters.as_ptr().cast();
+        assert!(try_from_raw_parts::<Word>(address, 2).is_ok());
+        assert_eq!(try_from_raw_parts::<Word>(address, 0).err(), Some(Empty));
+        assert!(try_from_raw_parts::<Unchecked>(address, 0).is_ok());
+
+        let bytes = [1u8, b'a', 0];
+        let mut reader = DstReader::<[u8], u8>::new(&bytes);
+        assert_eq!(reader.next(), Some(Ok(&b"a"[..])));
+        assert_eq!(reader.next(), Some(Ok(&b""[..])));
+
+        // SAFETY: `Word` is plain bytes.
+        unsafe impl crate::Plain for Word {
+            unsafe fn is_valid(_: *const Self) -> bool {
+                true
+            }
+
+            fn write_bytes(&self, out: &mut [u8]) {
+                out.copy_from_slice(&self.letters);
+            }
+        }
+
+        let mut reader = DstReader::<Word, u8>::new(&bytes);
+        assert_eq!(reader.next().unwrap().unwrap().letters, *b"a");
+        assert_eq!(
+            reader.next().unwrap().err(),
+            Some(RecordError::InvalidMetadata(Empty)),
+        );
+
+        #[cfg(feature = "alloc")]
+        {
+            use alloc::boxed::Box;
+
+            use crate::DstBuilder;
+
+            let word: Result<Box<Word>, _> =
+                DstBuilder::new(()).try_build_from_slice(b"ok");
+            assert_eq!(&word.unwrap().letters, b"ok");
+            let empty: Result<Box<Word>, _> =
+                DstBuilder::new(()).try_build(core::iter::empty());
+            assert_eq!(empty.err(), Some(Empty));
+        }
+    }
+}
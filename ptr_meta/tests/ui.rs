@@ -0,0 +1,10 @@
+#![cfg(feature = "derive")]
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+    #[cfg(not(ptr_meta_trait_upcasting))]
+    t.compile_fail("tests/ui/no_trait_upcasting/*.rs");
+}
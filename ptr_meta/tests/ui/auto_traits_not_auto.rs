@@ -0,0 +1,4 @@
+#[ptr_meta::pointee(auto_traits(Send, Debug))]
+trait Shape {}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use ptr_meta::Pointee;
+
+#[derive(Pointee)]
+enum Shape {
+    Square(f32),
+    Circle(f32),
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use ptr_meta::Pointee;
+
+#[derive(Pointee)]
+struct Unit;
+
+fn main() {}
@@ -0,0 +1,10 @@
+use ptr_meta::Pointee;
+
+#[derive(Pointee)]
+#[ptr_meta(auto_traits, skip_check, upcast(Debug))]
+struct Packet {
+    len: u32,
+    payload: [u8],
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use ptr_meta::Pointee;
+
+#[derive(Pointee)]
+union Bits {
+    int: u32,
+    float: f32,
+}
+
+fn main() {}
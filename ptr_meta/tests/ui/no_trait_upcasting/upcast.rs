@@ -0,0 +1,20 @@
+use core::fmt::Debug;
+
+use ptr_meta::UpcastMetadata;
+
+#[ptr_meta::pointee(upcast(Debug))]
+trait Shape: Debug {}
+
+#[derive(Debug)]
+struct Square;
+
+impl Shape for Square {}
+
+impl Shape for str {}
+
+fn main() {
+    let shape: &dyn Shape = &Square;
+    let _ = <dyn Shape as UpcastMetadata<dyn Debug>>::upcast_metadata(
+        ptr_meta::metadata(shape),
+    );
+}
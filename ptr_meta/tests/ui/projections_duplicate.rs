@@ -0,0 +1,20 @@
+use ptr_meta::Pointee;
+
+#[derive(Pointee)]
+#[ptr_meta(projections)]
+#[repr(C)]
+struct Block {
+    len: u8,
+    len_mut: u8,
+    elements: [u8],
+}
+
+#[derive(Pointee)]
+#[ptr_meta(projections)]
+#[repr(C)]
+struct Node {
+    tail_mut: u8,
+    elements: [u8],
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use ptr_meta::Pointee;
+
+#[derive(Pointee)]
+struct Node<T> {
+    header: u32,
+    tail: T,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use ptr_meta::Pointee;
+
+#[derive(Pointee)]
+struct Header {
+    len: usize,
+    bytes: [u8; 4],
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use ptr_meta::Pointee;
+
+#[derive(Pointee)]
+#[ptr_meta(tail_variants([u8], u32))]
+struct Node<T: ?Sized> {
+    header: u32,
+    tail: T,
+}
+
+fn main() {}
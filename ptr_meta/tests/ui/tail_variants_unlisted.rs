@@ -0,0 +1,14 @@
+use ptr_meta::Pointee;
+
+#[derive(Pointee)]
+#[ptr_meta(tail_variants([u8], str))]
+struct Node<T: ?Sized> {
+    header: u32,
+    tail: T,
+}
+
+fn assert_pointee<T: Pointee + ?Sized>() {}
+
+fn main() {
+    assert_pointee::<Node<[u32]>>();
+}
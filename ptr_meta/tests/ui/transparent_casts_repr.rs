@@ -0,0 +1,10 @@
+use ptr_meta::Pointee;
+
+#[derive(Pointee)]
+#[ptr_meta(transparent_casts)]
+#[repr(C)]
+struct Wrapper {
+    inner: [u8],
+}
+
+fn main() {}
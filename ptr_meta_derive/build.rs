@@ -0,0 +1,20 @@
+use std::{env, process::Command};
+
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(ptr_meta_trait_upcasting)");
+    println!("cargo:rerun-if-changed=build.rs");
+
+    // Trait upcasting coercions were stabilized in Rust 1.86. Proc macros are
+    // built by the same compiler as the code they expand into.
+    if rustc_minor_version().is_some_and(|minor| minor >= 86) {
+        println!("cargo:rustc-cfg=ptr_meta_trait_upcasting");
+    }
+}
+
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(output.stdout).ok()?;
+    let mut parts = version.strip_prefix("rustc 1.")?.split('.');
+    parts.next()?.parse().ok()
+}
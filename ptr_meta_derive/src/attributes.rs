@@ -1,7 +1,8 @@
 use quote::ToTokens;
 use syn::{
-    meta::ParseNestedMeta, parse_quote, AttrStyle, Attribute, Error, Path,
-    Token,
+    meta::ParseNestedMeta, parenthesized, parse::Parse, parse_quote,
+    parse_quote_spanned, punctuated::Punctuated, spanned::Spanned, token,
+    AttrStyle, Attribute, Error, Member, Path, Token, Type,
 };
 
 fn try_set_attribute<T: ToTokens>(
@@ -20,9 +21,46 @@ fn try_set_attribute<T: ToTokens>(
     }
 }
 
+// Adds an error to `errors` if an argument which is not supported by `macro_`
+// was specified.
+fn reject_attribute<T: ToTokens>(
+    errors: &mut Vec<Error>,
+    attribute: &Option<T>,
+    name: &'static str,
+    macro_: &'static str,
+) {
+    if let Some(value) = attribute {
+        errors.push(Error::new_spanned(
+            value,
+            format!("`{name}` is not supported by `{macro_}`"),
+        ));
+    }
+}
+
+fn combine_errors(errors: Vec<Error>) -> Result<(), Error> {
+    match errors.into_iter().reduce(|mut errors, error| {
+        errors.combine(error);
+        errors
+    }) {
+        Some(errors) => Err(errors),
+        None => Ok(()),
+    }
+}
+
 #[derive(Default)]
 pub struct Attributes {
     crate_path: Option<Path>,
+    pub clone_unsized: Option<Path>,
+    pub plain: Option<Path>,
+    pub transparent_casts: Option<Path>,
+    pub projections: Option<Path>,
+    pub len_field: Option<Member>,
+    pub validate: Option<Path>,
+    pub validate_error: Option<Type>,
+    pub tail_variants: Option<Punctuated<Type, Token![,]>>,
+    pub auto_traits: Option<Punctuated<Path, Token![,]>>,
+    pub skip_check: Option<Path>,
+    pub upcast: Option<Punctuated<Path, Token![,]>>,
 }
 
 impl Attributes {
@@ -43,6 +81,63 @@ impl Attributes {
             } else {
                 Err(meta.error("expected `crate` or `crate = ...`"))
             }
+        } else if meta.path.is_ident("clone_unsized") {
+            try_set_attribute(
+                &mut self.clone_unsized,
+                meta.path,
+                "clone_unsized",
+            )
+        } else if meta.path.is_ident("plain") {
+            try_set_attribute(&mut self.plain, meta.path, "plain")
+        } else if meta.path.is_ident("transparent_casts") {
+            try_set_attribute(
+                &mut self.transparent_casts,
+                meta.path,
+                "transparent_casts",
+            )
+        } else if meta.path.is_ident("projections") {
+            try_set_attribute(&mut self.projections, meta.path, "projections")
+        } else if meta.path.is_ident("len_field") {
+            let member = meta.value()?.parse::<Member>()?;
+            try_set_attribute(&mut self.len_field, member, "len_field")
+        } else if meta.path.is_ident("validate") {
+            let path = meta.value()?.parse::<Path>()?;
+            try_set_attribute(&mut self.validate, path, "validate")
+        } else if meta.path.is_ident("validate_error") {
+            let ty = meta.value()?.parse::<Type>()?;
+            try_set_attribute(&mut self.validate_error, ty, "validate_error")
+        } else if meta.path.is_ident("auto_traits") {
+            let auto_traits = if meta.input.peek(token::Paren) {
+                let content;
+                parenthesized!(content in meta.input);
+                content.parse_terminated(Path::parse, Token![,])?
+            } else {
+                parse_quote_spanned! { meta.path.span() => Send, Sync }
+            };
+            try_set_attribute(&mut self.auto_traits, auto_traits, "auto_traits")
+        } else if meta.path.is_ident("skip_check") {
+            try_set_attribute(&mut self.skip_check, meta.path, "skip_check")
+        } else if meta.path.is_ident("upcast") {
+            let content;
+            parenthesized!(content in meta.input);
+            let supertraits =
+                content.parse_terminated(Path::parse, Token![,])?;
+            if supertraits.is_empty() {
+                return Err(meta.error("expected at least one supertrait"));
+            }
+            try_set_attribute(&mut self.upcast, supertraits, "upcast")
+        } else if meta.path.is_ident("tail_variants") {
+            let content;
+            parenthesized!(content in meta.input);
+            let variants = content.parse_terminated(Type::parse, Token![,])?;
+            if variants.is_empty() {
+                return Err(meta.error("expected at least one tail type"));
+            }
+            try_set_attribute(
+                &mut self.tail_variants,
+                variants,
+                "tail_variants",
+            )
         } else {
             Err(meta.error("unrecognized ptr_meta argument"))
         }
@@ -64,6 +159,42 @@ impl Attributes {
         Ok(result)
     }
 
+    /// Returns an error for each argument which only applies to
+    /// `#[derive(Pointee)]`.
+    pub fn check_pointee_args(&self) -> Result<(), Error> {
+        const MACRO: &str = "#[ptr_meta::pointee]";
+
+        let mut errors = Vec::new();
+        let e = &mut errors;
+        reject_attribute(e, &self.clone_unsized, "clone_unsized", MACRO);
+        reject_attribute(e, &self.plain, "plain", MACRO);
+        reject_attribute(
+            e,
+            &self.transparent_casts,
+            "transparent_casts",
+            MACRO,
+        );
+        reject_attribute(e, &self.projections, "projections", MACRO);
+        reject_attribute(e, &self.len_field, "len_field", MACRO);
+        reject_attribute(e, &self.validate, "validate", MACRO);
+        reject_attribute(e, &self.validate_error, "validate_error", MACRO);
+        reject_attribute(e, &self.tail_variants, "tail_variants", MACRO);
+        combine_errors(errors)
+    }
+
+    /// Returns an error for each argument which only applies to
+    /// `#[ptr_meta::pointee]`.
+    pub fn check_derive_args(&self) -> Result<(), Error> {
+        const MACRO: &str = "#[derive(Pointee)]";
+
+        let mut errors = Vec::new();
+        let e = &mut errors;
+        reject_attribute(e, &self.auto_traits, "auto_traits", MACRO);
+        reject_attribute(e, &self.skip_check, "skip_check", MACRO);
+        reject_attribute(e, &self.upcast, "upcast", MACRO);
+        combine_errors(errors)
+    }
+
     pub fn crate_path(&self) -> Path {
         self.crate_path
             .clone()
@@ -0,0 +1,171 @@
+use syn::{
+    visit::{self, Visit},
+    Error, FnArg, GenericParam, Ident, ItemTrait, ReturnType, TraitItem,
+    TraitItemFn, Type, TypeParamBound,
+};
+
+use crate::object::{is_sized_bound, requires_sized};
+
+/// Returns an error for each item of a trait which makes it dyn-incompatible.
+///
+/// rustc reports these violations on the generated impls for the trait object
+/// instead of on their causes, so they're checked up front.
+pub fn check_dyn_compatible(item: &ItemTrait) -> Result<(), Error> {
+    let trait_ident = &item.ident;
+    let mut errors = Vec::new();
+
+    let sized_supertrait = item.supertraits.iter().find(|bound| {
+        matches!(bound, TypeParamBound::Trait(_)) && is_sized_bound(bound)
+    });
+    if let Some(bound) = sized_supertrait {
+        errors.push(Error::new_spanned(
+            bound,
+            format!(
+                "the trait `{trait_ident}` is not dyn-compatible because it \
+                 requires `Self: Sized`\nhelp: remove the `Sized` supertrait \
+                 and add `where Self: Sized` to the methods which need it"
+            ),
+        ));
+    } else if requires_sized(&item.generics) {
+        errors.push(Error::new_spanned(
+            &item.generics.where_clause,
+            format!(
+                "the trait `{trait_ident}` is not dyn-compatible because it \
+                 requires `Self: Sized`\nhelp: remove the `Self: Sized` bound \
+                 and add `where Self: Sized` to the methods which need it"
+            ),
+        ));
+    }
+
+    for trait_item in item.items.iter() {
+        match trait_item {
+            TraitItem::Const(item) => errors.push(Error::new_spanned(
+                &item.ident,
+                format!(
+                    "the trait `{trait_ident}` is not dyn-compatible because \
+                     it contains the associated const `{}`\nhelp: replace the \
+                     const with a method which returns its value",
+                    item.ident,
+                ),
+            )),
+            TraitItem::Fn(method) if !requires_sized(&method.sig.generics) => {
+                check_method(trait_ident, method, &mut errors)
+            }
+            _ => (),
+        }
+    }
+
+    match errors.into_iter().reduce(|mut errors, error| {
+        errors.combine(error);
+        errors
+    }) {
+        Some(errors) => Err(errors),
+        None => Ok(()),
+    }
+}
+
+fn check_method(
+    trait_ident: &Ident,
+    method: &TraitItemFn,
+    errors: &mut Vec<Error>,
+) {
+    let sig = &method.sig;
+    let ident = &sig.ident;
+    let mut error = |tokens: &dyn quote::ToTokens, reason: &str| {
+        errors.push(Error::new_spanned(
+            tokens,
+            format!(
+                "the trait `{trait_ident}` is not dyn-compatible because \
+                 {reason}\nhelp: add `where Self: Sized` to `{ident}` to \
+                 exclude it from the trait object"
+            ),
+        ));
+    };
+
+    if sig.receiver().is_none() {
+        error(ident, &format!("`{ident}` has no `self` parameter"));
+    }
+    if let Some(asyncness) = &sig.asyncness {
+        error(asyncness, &format!("method `{ident}` is `async`"));
+    }
+    if let Some(param) = sig
+        .generics
+        .params
+        .iter()
+        .find(|param| !matches!(param, GenericParam::Lifetime(_)))
+    {
+        error(param, &format!("method `{ident}` has generic parameters"));
+    }
+
+    for input in sig.inputs.iter() {
+        let FnArg::Typed(input) = input else {
+            continue;
+        };
+        let found = find_types(&input.ty);
+        if let Some(ty) = found.impl_trait {
+            error(
+                ty,
+                &format!("method `{ident}` has an `impl Trait` parameter"),
+            );
+        }
+        if let Some(ty) = found.self_ty {
+            error(
+                ty,
+                &format!(
+                    "method `{ident}` references the `Self` type in its \
+                     parameters"
+                ),
+            );
+        }
+    }
+
+    if let ReturnType::Type(_, ty) = &sig.output {
+        let found = find_types(ty);
+        if let Some(ty) = found.impl_trait {
+            error(ty, &format!("method `{ident}` returns `impl Trait`"));
+        }
+        if let Some(ty) = found.self_ty {
+            error(
+                ty,
+                &format!(
+                    "method `{ident}` references the `Self` type in its \
+                     return type"
+                ),
+            );
+        }
+    }
+}
+
+fn find_types(ty: &Type) -> FindTypes<'_> {
+    let mut find = FindTypes::default();
+    find.visit_type(ty);
+    find
+}
+
+// Finds the first uses of `Self` and `impl Trait` in a type. Projections like
+// `Self::Item` are allowed in trait objects, so they don't count as `Self`.
+#[derive(Default)]
+struct FindTypes<'ast> {
+    self_ty: Option<&'ast Type>,
+    impl_trait: Option<&'ast Type>,
+}
+
+impl<'ast> Visit<'ast> for FindTypes<'ast> {
+    fn visit_type(&mut self, ty: &'ast Type) {
+        match ty {
+            Type::Path(path) if path.qself.is_some() => {
+                self.visit_path(&path.path);
+                return;
+            }
+            Type::Path(path) if path.path.is_ident("Self") => {
+                self.self_ty.get_or_insert(ty);
+            }
+            Type::ImplTrait(_) => {
+                self.impl_trait.get_or_insert(ty);
+            }
+            _ => (),
+        }
+
+        visit::visit_type(self, ty);
+    }
+}
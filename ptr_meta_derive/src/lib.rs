@@ -1,12 +1,25 @@
 mod attributes;
+mod dyn_compat;
+mod object;
+mod tail;
+mod target;
+mod upcast;
 
-use proc_macro2::TokenStream;
-use quote::quote;
+use std::collections::HashMap;
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::{
-    meta, parse_macro_input, parse_quote, Data, DeriveInput, Error, ItemTrait,
+    meta, parse_macro_input, parse_quote, punctuated::Punctuated,
+    spanned::Spanned, Data, DeriveInput, Error, Field, Fields, Ident,
+    ItemTrait, Member, Meta, Path, Token, Type,
 };
 
-use self::attributes::Attributes;
+use self::{
+    attributes::Attributes, dyn_compat::check_dyn_compatible,
+    object::TraitObject, tail::check_tail, target::Target,
+    upcast::pointee_upcasts,
+};
 
 /// Derives `Pointee` for the labeled struct which has a trailing DST.
 ///
@@ -17,6 +30,44 @@ use self::attributes::Attributes;
 /// `#[ptr_meta(...)]` accepts the following arguments:
 ///
 /// - `crate = ...`: Chooses an alternative crate path to import ptr_meta from.
+/// - `clone_unsized`: Implements `CloneUnsized` and `ToOwned` for the struct.
+///   Every field except the last must be `Clone`, and the last field must be
+///   `CloneUnsized`.
+/// - `plain`: Implements `Plain` for the struct. The struct must be
+///   `#[repr(C)]` or `#[repr(transparent)]`, and every field must be `Plain`.
+/// - `transparent_casts`: Implements `Transparent` for the struct, which
+///   converts references and smart pointers to and from its last field. The
+///   struct must be `#[repr(transparent)]`.
+/// - `projections`: Generates unsafe functions which project raw pointers to
+///   the struct into raw pointers to its fields without creating references.
+///   For each field `field` except the last, `project_field`,
+///   `project_field_mut`, and `project_field_non_null` are generated. The last
+///   field is projected by `project_tail`, `project_tail_mut`, and
+///   `project_tail_non_null`. Every generated function must have a distinct
+///   name.
+/// - `len_field = ...`: Implements `ThinDst` for the struct, which reads the
+///   length of its tail from the named field. The last field must be a slice or
+///   `str`, and the named field must be a `CompactLen`.
+/// - `validate = ...` and `validate_error = ...`: Validates the metadata of the
+///   struct in checked APIs by calling the named function, which must take the
+///   metadata and return a `Result<(), E>` where `E` is the type given by
+///   `validate_error`. Without them, the metadata is validated as the metadata
+///   of the last field.
+/// - `tail_variants(...)`: Implements `Pointee` for each of the listed types as
+///   the last field. The last field must be a generic type parameter, which is
+///   replaced with each listed type in turn. Other instantiations with an
+///   unsized last field do not implement `Pointee`.
+///
+/// # Slice DSTs
+///
+/// If the struct is `#[repr(C)]` or `#[repr(transparent)]`,
+/// `LayoutFromMetadata` is also implemented for it. If its last field is
+/// additionally a slice or `str`, `SliceDst` is implemented as well.
+///
+/// Structs whose last field is a slice or `str` implement `SliceLikePointee`
+/// regardless of their representation.
+///
+/// `ValidateMetadata` is implemented for every struct.
 #[proc_macro_derive(Pointee, attributes(ptr_meta))]
 pub fn derive_pointee(
     input: proc_macro::TokenStream,
@@ -29,25 +80,30 @@ pub fn derive_pointee(
     }
 }
 
-fn derive_pointee_impl(mut input: DeriveInput) -> Result<TokenStream, Error> {
+fn derive_pointee_impl(input: DeriveInput) -> Result<TokenStream, Error> {
     let attributes = Attributes::parse(&input.attrs)?;
+    attributes.check_derive_args()?;
     let ident = &input.ident;
     let crate_path = attributes.crate_path();
 
     let fields = match &input.data {
         Data::Struct(data) => &data.fields,
-        Data::Enum(_) => {
-            return Err(Error::new(
-                ident.span(),
-                "enums always have a provided `Pointee` impl because they \
-                 cannot be dynamically-sized",
+        Data::Enum(data) => {
+            return Err(Error::new_spanned(
+                data.enum_token,
+                "enums are always sized, so they already implement `Pointee` \
+                 through the impl for sized types\nhelp: remove \
+                 `#[derive(Pointee)]`, or use a trait object with \
+                 `#[ptr_meta::pointee]` on its trait to store values of \
+                 different types behind one pointer type",
             ))
         }
-        Data::Union(_) => {
-            return Err(Error::new(
-                ident.span(),
-                "unions always have an provided `Pointee` impl because they \
-                 cannot be dynamically-sized",
+        Data::Union(data) => {
+            return Err(Error::new_spanned(
+                data.union_token,
+                "unions are always sized, so they already implement `Pointee` \
+                 through the impl for sized types\nhelp: remove \
+                 `#[derive(Pointee)]`",
             ))
         }
     };
@@ -55,36 +111,820 @@ fn derive_pointee_impl(mut input: DeriveInput) -> Result<TokenStream, Error> {
     let Some(last_field) = fields.iter().next_back() else {
         return Err(Error::new(
             ident.span(),
-            "fieldless structs always have a provided `Poitnee` impl because
-            they cannot be dynamically-sized",
+            "fieldless structs are always sized, so they already implement \
+             `Pointee` through the impl for sized types\nhelp: remove \
+             `#[derive(Pointee)]`, or add a dynamically-sized last field",
         ));
     };
-    let last_field_ty = &last_field.ty;
+    if attributes.tail_variants.is_none() {
+        check_tail(&last_field.ty, &input.generics)?;
+    }
+
+    let c_layout = has_c_layout(&input)?;
+    if let Some(plain) = &attributes.plain {
+        if !c_layout {
+            return Err(Error::new_spanned(
+                plain,
+                "`plain` requires the struct to be `#[repr(C)]` or \
+                 `#[repr(transparent)]`\nhelp: add `#[repr(C)]` to the struct",
+            ));
+        }
+    }
+
+    if let Some(transparent_casts) = &attributes.transparent_casts {
+        if !is_transparent(&input)? {
+            return Err(Error::new_spanned(
+                transparent_casts,
+                "`transparent_casts` requires the struct to be \
+                 `#[repr(transparent)]`\nhelp: add `#[repr(transparent)]` to \
+                 the struct",
+            ));
+        }
+    }
+
+    if attributes.projections.is_some() {
+        check_projection_names(fields)?;
+    }
+
+    match (&attributes.validate, &attributes.validate_error) {
+        (Some(validate), None) => {
+            return Err(Error::new_spanned(
+                validate,
+                "`validate` requires the error type of the function to be \
+                 specified with `validate_error = ...`",
+            ));
+        }
+        (None, Some(validate_error)) => {
+            return Err(Error::new_spanned(
+                validate_error,
+                "`validate_error` requires a function to be specified with \
+                 `validate = ...`",
+            ));
+        }
+        _ => (),
+    }
+
+    let targets = match &attributes.tail_variants {
+        Some(variants) => Target::tail_variants(&input, fields, variants)?,
+        None => vec![Target::new(&input, fields)],
+    };
+    if attributes.tail_variants.is_some() {
+        for target in targets.iter() {
+            check_tail(target.tail_ty(), &target.generics)?;
+        }
+    }
+
+    if let Some(len_field) = &attributes.len_field {
+        let header_members = fields.members().take(fields.len() - 1);
+        if !header_members.clone().any(|member| member == *len_field) {
+            return Err(Error::new_spanned(
+                len_field,
+                "`len_field` must name a field other than the last",
+            ));
+        }
+        if let Some(target) = targets
+            .iter()
+            .find(|target| !is_slice_tail(target.tail_ty()))
+        {
+            return Err(Error::new_spanned(
+                target.tail_ty(),
+                "`len_field` requires the last field to be a slice or `str`",
+            ));
+        }
+    }
+
+    let mut result = TokenStream::new();
+    for target in targets.iter() {
+        result.extend(derive_target(
+            target,
+            &attributes,
+            c_layout,
+            &crate_path,
+        ));
+    }
+    Ok(result)
+}
+
+fn derive_target(
+    target: &Target,
+    attributes: &Attributes,
+    c_layout: bool,
+    crate_path: &Path,
+) -> TokenStream {
+    let tail_ty = target.tail_ty();
+
+    let mut extra_impls = TokenStream::new();
+    if attributes.clone_unsized.is_some() {
+        extra_impls.extend(derive_clone_unsized(target, crate_path));
+    }
+    if attributes.plain.is_some() {
+        extra_impls.extend(derive_plain(target, crate_path));
+    }
+    if attributes.transparent_casts.is_some() {
+        extra_impls.extend(derive_transparent(target, crate_path));
+    }
+    extra_impls
+        .extend(derive_validate_metadata(target, attributes, crate_path));
+    if attributes.projections.is_some() {
+        extra_impls.extend(derive_projections(target, crate_path));
+    }
+    if let Some(len_field) = &attributes.len_field {
+        extra_impls.extend(derive_thin_dst(target, len_field, crate_path));
+    }
+    if is_slice_tail(tail_ty) {
+        extra_impls.extend(derive_slice_like_pointee(target, crate_path));
+    }
+    if c_layout {
+        extra_impls.extend(derive_layout_from_metadata(target, crate_path));
+        if is_slice_tail(tail_ty) {
+            extra_impls.extend(derive_slice_dst(target, crate_path));
+        }
+    }
+
+    let mut generics = target.generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote! { #tail_ty: #crate_path::Pointee });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let self_ty = &target.self_ty;
+
+    // Spanning the impl for a tail variant at the type listed in
+    // `tail_variants` makes errors for unlisted tails point at the list.
+    let span = target
+        .variant
+        .as_ref()
+        .map_or_else(Span::call_site, Spanned::span);
+    quote_spanned! { span =>
+        // SAFETY: The pointer metadata of a struct is the pointer metadata of
+        // its last field.
+        unsafe impl #impl_generics #crate_path::Pointee for #self_ty
+        #where_clause
+        {
+            type Metadata = <#tail_ty as #crate_path::Pointee>::Metadata;
+        }
+
+        #extra_impls
+    }
+}
+
+fn is_slice_tail(ty: &Type) -> bool {
+    match ty {
+        Type::Slice(_) => true,
+        Type::Path(path) => path.qself.is_none() && path.path.is_ident("str"),
+        Type::Group(group) => is_slice_tail(&group.elem),
+        Type::Paren(paren) => is_slice_tail(&paren.elem),
+        _ => false,
+    }
+}
+
+// Returns whether the struct is laid out with its fields in declaration order.
+fn has_c_layout(input: &DeriveInput) -> Result<bool, Error> {
+    let mut ordered = false;
+    for attr in input.attrs.iter() {
+        if !attr.path().is_ident("repr") {
+            continue;
+        }
+
+        let reprs = attr
+            .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+        for repr in reprs.iter() {
+            let path = repr.path();
+            if path.is_ident("C") || path.is_ident("transparent") {
+                ordered = true;
+            } else if path.is_ident("packed") || path.is_ident("align") {
+                return Ok(false);
+            }
+        }
+    }
+
+    Ok(ordered)
+}
 
-    let where_clause = input.generics.make_where_clause();
+// Returns whether the struct is `#[repr(transparent)]`.
+//
+// The compiler requires every other field of a transparent struct to be a
+// zero-sized type with an alignment of 1. The last field of a struct which
+// derives `Pointee` is unsized, so it must be the field the struct is
+// transparent over.
+fn is_transparent(input: &DeriveInput) -> Result<bool, Error> {
+    for attr in input.attrs.iter() {
+        if !attr.path().is_ident("repr") {
+            continue;
+        }
+
+        let reprs = attr
+            .parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)?;
+        if reprs.iter().any(|repr| repr.path().is_ident("transparent")) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+fn derive_validate_metadata(
+    target: &Target,
+    attributes: &Attributes,
+    crate_path: &Path,
+) -> TokenStream {
+    let self_ty = &target.self_ty;
+    let tail_ty = target.tail_ty();
+
+    let mut generics = target.generics.clone();
+    let where_clause = generics.make_where_clause();
     where_clause
         .predicates
-        .push(parse_quote! { #last_field_ty: #crate_path::Pointee });
+        .push(parse_quote! { #tail_ty: #crate_path::Pointee });
+    let (error_ty, validate) =
+        match (&attributes.validate, &attributes.validate_error) {
+            (Some(validate), Some(error_ty)) => {
+                (quote! { #error_ty }, quote! { #validate(metadata) })
+            }
+            _ => {
+                where_clause.predicates.push(
+                    target.bound(
+                        tail_ty,
+                        quote! { #crate_path::ValidateMetadata },
+                    ),
+                );
+                (
+                    quote! {
+                        <#tail_ty as #crate_path::ValidateMetadata>::Error
+                    },
+                    quote! {
+                        <#tail_ty as #crate_path::ValidateMetadata>
+                            ::validate_metadata(metadata)
+                    },
+                )
+            }
+        };
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #crate_path::ValidateMetadata for #self_ty
+        #where_clause
+        {
+            type Error = #error_ty;
+
+            #[inline]
+            fn validate_metadata(
+                metadata: <Self as #crate_path::Pointee>::Metadata,
+            ) -> ::core::result::Result<(), Self::Error> {
+                #validate
+            }
+        }
+    }
+}
+
+// Returns the name used for the projections of a field.
+fn projection_name(fields: &Fields, index: usize, field: &Field) -> String {
+    let name = match &field.ident {
+        _ if index == fields.len() - 1 => "tail".to_string(),
+        Some(ident) => ident.to_string(),
+        None => index.to_string(),
+    };
+    match name.strip_prefix("r#") {
+        Some(name) => name.to_string(),
+        None => name,
+    }
+}
+
+// Returns an error if two fields would generate projections with the same
+// name.
+fn check_projection_names(fields: &Fields) -> Result<(), Error> {
+    let mut generated = HashMap::new();
+    for (i, field) in fields.iter().enumerate() {
+        let name = projection_name(fields, i, field);
+        for suffix in ["", "_mut", "_non_null"] {
+            let function = format!("project_{name}{suffix}");
+            if let Some(other) = generated.insert(function.clone(), i) {
+                let describe = |index: usize| {
+                    let field = fields.iter().nth(index).unwrap();
+                    if index == fields.len() - 1 {
+                        "the last field".to_string()
+                    } else {
+                        format!("`{}`", projection_name(fields, index, field))
+                    }
+                };
+                return Err(Error::new_spanned(
+                    field,
+                    format!(
+                        "`projections` would generate `{function}` for both \
+                         {} and {}\nhelp: rename one of the fields",
+                        describe(other),
+                        describe(i),
+                    ),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn derive_projections(target: &Target, crate_path: &Path) -> TokenStream {
+    let self_ty = &target.self_ty;
+    let fields = &target.fields;
+    let tail_ty = target.tail_ty();
+
+    let mut generics = target.generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote! { #tail_ty: #crate_path::Pointee });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    let safety = "# Safety\n\n`ptr` must point into a single allocation which \
+                  is large enough for a value of the struct with the metadata \
+                  of `ptr`. The memory does not need to be initialized.";
+
+    let mut functions = TokenStream::new();
+    for (i, (field, member)) in fields.iter().zip(fields.members()).enumerate()
+    {
+        let vis = &field.vis;
+        let is_tail = i == fields.len() - 1;
+        let name = projection_name(fields, i, field);
+        let project = format_ident!("project_{name}");
+        let project_mut = format_ident!("project_{name}_mut");
+        let project_non_null = format_ident!("project_{name}_non_null");
+        let doc = format!("Projects a pointer to the struct to its `{name}`.");
+        let doc_mut = format!(
+            "Projects a mutable pointer to the struct to its `{name}`."
+        );
+        let doc_non_null =
+            format!("Projects a `NonNull` to the struct to its `{name}`.");
+        let ty = &field.ty;
+
+        let (project_body, project_mut_body) = if is_tail {
+            (
+                quote! {
+                    // SAFETY: The caller has guaranteed that `ptr` points into
+                    // an allocation which is large enough for the struct, so
+                    // the last field is in bounds.
+                    let tail = unsafe { ::core::ptr::addr_of!((*ptr).#member) };
+                    #crate_path::from_raw_parts(
+                        tail.cast::<()>(),
+                        #crate_path::metadata(ptr),
+                    )
+                },
+                quote! {
+                    // SAFETY: The caller has guaranteed that `ptr` points into
+                    // an allocation which is large enough for the struct, so
+                    // the last field is in bounds.
+                    let tail =
+                        unsafe { ::core::ptr::addr_of_mut!((*ptr).#member) };
+                    #crate_path::from_raw_parts_mut(
+                        tail.cast::<()>(),
+                        #crate_path::metadata(ptr),
+                    )
+                },
+            )
+        } else {
+            (
+                quote! {
+                    // SAFETY: The caller has guaranteed that `ptr` points into
+                    // an allocation which is large enough for the struct, so
+                    // the field is in bounds.
+                    unsafe { ::core::ptr::addr_of!((*ptr).#member) }
+                },
+                quote! {
+                    // SAFETY: The caller has guaranteed that `ptr` points into
+                    // an allocation which is large enough for the struct, so
+                    // the field is in bounds.
+                    unsafe { ::core::ptr::addr_of_mut!((*ptr).#member) }
+                },
+            )
+        };
+
+        functions.extend(quote! {
+            #[doc = #doc]
+            ///
+            #[doc = #safety]
+            #[inline]
+            #vis unsafe fn #project(ptr: *const Self) -> *const #ty {
+                #project_body
+            }
 
-    let (impl_generics, ty_generics, where_clause) =
-        input.generics.split_for_impl();
+            #[doc = #doc_mut]
+            ///
+            #[doc = #safety]
+            #[inline]
+            #vis unsafe fn #project_mut(ptr: *mut Self) -> *mut #ty {
+                #project_mut_body
+            }
 
-    Ok(quote! {
-        unsafe impl #impl_generics #crate_path::Pointee for #ident #ty_generics
+            #[doc = #doc_non_null]
+            ///
+            #[doc = #safety]
+            #[inline]
+            #vis unsafe fn #project_non_null(
+                ptr: ::core::ptr::NonNull<Self>,
+            ) -> ::core::ptr::NonNull<#ty> {
+                // SAFETY: The caller has upheld the same requirements for
+                // `ptr`.
+                let field = unsafe { Self::#project_mut(ptr.as_ptr()) };
+                // SAFETY: `field` is in bounds of the allocation `ptr` points
+                // into, so it is not null.
+                unsafe { ::core::ptr::NonNull::new_unchecked(field) }
+            }
+        });
+    }
+
+    quote! {
+        impl #impl_generics #self_ty #where_clause {
+            #functions
+        }
+    }
+}
+
+fn derive_thin_dst(
+    target: &Target,
+    len_field: &Member,
+    crate_path: &Path,
+) -> TokenStream {
+    let self_ty = &target.self_ty;
+    let len_ty = &target
+        .fields
+        .iter()
+        .zip(target.fields.members())
+        .find(|(_, member)| member == len_field)
+        .unwrap()
+        .0
+        .ty;
+
+    let mut generics = target.generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote! { #len_ty: #crate_path::CompactLen });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    quote! {
+        unsafe impl #impl_generics #crate_path::ThinDst for #self_ty
+        #where_clause
+        {
+            #[inline]
+            unsafe fn read_len(ptr: *const ()) -> usize {
+                let ptr = #crate_path::from_raw_parts::<Self>(ptr, 0);
+                let len = unsafe {
+                    ::core::ptr::addr_of!((*ptr).#len_field).read()
+                };
+                <#len_ty as #crate_path::CompactLen>::to_len(len)
+            }
+        }
+    }
+}
+
+fn derive_transparent(target: &Target, crate_path: &Path) -> TokenStream {
+    let self_ty = &target.self_ty;
+    let tail_ty = target.tail_ty();
+
+    let mut generics = target.generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote! { #tail_ty: #crate_path::Pointee });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    quote! {
+        // SAFETY: `transparent_casts` is only accepted on structs which are
+        // `#[repr(transparent)]`, and the last field is the one they are
+        // transparent over because it is the only one which may be unsized.
+        unsafe impl #impl_generics #crate_path::Transparent for #self_ty
         #where_clause
         {
-            type Metadata = <#last_field_ty as #crate_path::Pointee>::Metadata;
+            type Inner = #tail_ty;
         }
-    })
+    }
+}
+
+fn derive_clone_unsized(target: &Target, crate_path: &Path) -> TokenStream {
+    let ident = &target.ident;
+    let fields = &target.fields;
+    let self_ty = &target.self_ty;
+    let members = fields.members().collect::<Vec<_>>();
+    let (header_members, tail_member) = members.split_at(members.len() - 1);
+    let tail_member = &tail_member[0];
+    let header_tys = fields
+        .iter()
+        .take(fields.len() - 1)
+        .map(|f| &f.ty)
+        .collect::<Vec<_>>();
+    let tail_ty = target.tail_ty();
+    let header_names = (0..header_tys.len())
+        .map(|i| Ident::new(&format!("__field_{i}"), ident.span()))
+        .collect::<Vec<_>>();
+    let offset_names = (0..header_tys.len())
+        .map(|i| Ident::new(&format!("__offset_{i}"), ident.span()))
+        .collect::<Vec<_>>();
+
+    let mut generics = target.generics.clone();
+    let where_clause = generics.make_where_clause();
+    for ty in header_tys.iter() {
+        where_clause
+            .predicates
+            .push(parse_quote! { #ty: ::core::clone::Clone });
+    }
+    where_clause
+        .predicates
+        .push(target.bound(tail_ty, quote! { #crate_path::CloneUnsized }));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #crate_path::CloneUnsized for #self_ty
+        #where_clause
+        {
+            fn clone_to_box(&self) -> #crate_path::__private::Box<Self> {
+                let base = self as *const Self;
+                #(
+                    let #header_names = ::core::clone::Clone::clone(
+                        &self.#header_members,
+                    );
+                    let #offset_names = #crate_path::__private::field_offset(
+                        base,
+                        &self.#header_members,
+                    );
+                )*
+                let tail_offset = #crate_path::__private::field_offset(
+                    base,
+                    &self.#tail_member,
+                );
+                let tail = <#tail_ty as #crate_path::CloneUnsized>
+                    ::clone_to_box(&self.#tail_member);
+                unsafe {
+                    #crate_path::__private::assemble_clone(
+                        self,
+                        tail,
+                        tail_offset,
+                        |ptr: *mut u8| {
+                            #(
+                                unsafe {
+                                    ptr.add(#offset_names)
+                                        .cast::<#header_tys>()
+                                        .write(#header_names);
+                                }
+                            )*
+                        },
+                    )
+                }
+            }
+        }
+
+        impl #impl_generics #crate_path::__private::ToOwned
+            for #self_ty
+        #where_clause
+        {
+            type Owned = #crate_path::__private::Box<Self>;
+
+            #[inline]
+            fn to_owned(&self) -> Self::Owned {
+                <Self as #crate_path::CloneUnsized>::clone_to_box(self)
+            }
+        }
+    }
+}
+
+fn derive_plain(target: &Target, crate_path: &Path) -> TokenStream {
+    let fields = &target.fields;
+    let self_ty = &target.self_ty;
+    let members = fields.members().collect::<Vec<_>>();
+    let tys = fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+
+    let mut generics = target.generics.clone();
+    let where_clause = generics.make_where_clause();
+    for ty in tys.iter() {
+        where_clause
+            .predicates
+            .push(target.bound(ty, quote! { #crate_path::Plain }));
+    }
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    quote! {
+        unsafe impl #impl_generics #crate_path::Plain for #self_ty
+        #where_clause
+        {
+            #[inline]
+            unsafe fn is_valid(ptr: *const Self) -> bool {
+                #(
+                    let field_ptr = unsafe {
+                        ::core::ptr::addr_of!((*ptr).#members)
+                    };
+                    if !unsafe {
+                        <#tys as #crate_path::Plain>::is_valid(field_ptr)
+                    } {
+                        return false;
+                    }
+                )*
+                true
+            }
+
+            #[inline]
+            fn write_bytes(&self, out: &mut [u8]) {
+                let base = self as *const Self;
+                #(
+                    let offset = #crate_path::__private::field_offset(
+                        base,
+                        &self.#members,
+                    );
+                    let size = ::core::mem::size_of_val(&self.#members);
+                    <#tys as #crate_path::Plain>::write_bytes(
+                        &self.#members,
+                        &mut out[offset..offset + size],
+                    );
+                )*
+            }
+        }
+    }
+}
+
+fn derive_slice_like_pointee(
+    target: &Target,
+    crate_path: &Path,
+) -> TokenStream {
+    let self_ty = &target.self_ty;
+    let tail_ty = target.tail_ty();
+    let (impl_generics, _, where_clause) = target.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #crate_path::SliceLikePointee
+            for #self_ty
+        #where_clause
+        {
+            type Element =
+                <#tail_ty as #crate_path::SliceLikePointee>::Element;
+        }
+    }
+}
+
+fn derive_layout_from_metadata(
+    target: &Target,
+    crate_path: &Path,
+) -> TokenStream {
+    let fields = &target.fields;
+    let self_ty = &target.self_ty;
+    let header_tys = fields
+        .iter()
+        .take(fields.len() - 1)
+        .map(|f| &f.ty)
+        .collect::<Vec<_>>();
+    let tail_ty = target.tail_ty();
+
+    let mut generics = target.generics.clone();
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote! { #tail_ty: #crate_path::LayoutFromMetadata });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    quote! {
+        unsafe impl #impl_generics #crate_path::LayoutFromMetadata
+            for #self_ty
+        #where_clause
+        {
+            const MIN_ALIGN: usize = {
+                let align = <#tail_ty as #crate_path::LayoutFromMetadata>
+                    ::MIN_ALIGN;
+                #(
+                    let field_align = ::core::mem::align_of::<#header_tys>();
+                    let align = if field_align > align {
+                        field_align
+                    } else {
+                        align
+                    };
+                )*
+                align
+            };
+
+            #[inline]
+            fn layout_for_metadata(
+                metadata: <Self as #crate_path::Pointee>::Metadata,
+            ) -> ::core::option::Option<::core::alloc::Layout> {
+                let layout = ::core::alloc::Layout::new::<()>();
+                #(
+                    let (layout, _) = layout
+                        .extend(::core::alloc::Layout::new::<#header_tys>())
+                        .ok()?;
+                )*
+                let tail = <#tail_ty as #crate_path::LayoutFromMetadata>
+                    ::layout_for_metadata(metadata)?;
+                let (layout, _) = layout.extend(tail).ok()?;
+                ::core::option::Option::Some(layout.pad_to_align())
+            }
+        }
+    }
+}
+
+fn derive_slice_dst(target: &Target, crate_path: &Path) -> TokenStream {
+    let ident = &target.ident;
+    let fields = &target.fields;
+    let self_ty = &target.self_ty;
+    let (impl_generics, _, where_clause) = target.generics.split_for_impl();
+
+    let header_fields = fields.iter().take(fields.len() - 1);
+    let header_tys = header_fields
+        .clone()
+        .map(|f: &Field| &f.ty)
+        .collect::<Vec<_>>();
+    let header_names = (0..header_tys.len())
+        .map(|i| Ident::new(&format!("__field_{i}"), ident.span()))
+        .collect::<Vec<_>>();
+    let tail_ty = target.tail_ty();
+
+    let (header_ty, header_pat) = match header_tys.as_slice() {
+        [ty] => (quote! { #ty }, quote! { #(#header_names)* }),
+        _ => (
+            quote! { (#(#header_tys,)*) },
+            quote! { (#(#header_names,)*) },
+        ),
+    };
+
+    quote! {
+        unsafe impl #impl_generics #crate_path::SliceDst for #self_ty
+        #where_clause
+        {
+            type Header = #header_ty;
+            type Tail = #tail_ty;
+
+            #[inline]
+            fn layout_for(
+                len: usize,
+            ) -> ::core::option::Option<(::core::alloc::Layout, usize)> {
+                let layout = ::core::alloc::Layout::new::<()>();
+                #(
+                    let (layout, _) = layout
+                        .extend(::core::alloc::Layout::new::<#header_tys>())
+                        .ok()?;
+                )*
+                let tail = ::core::alloc::Layout::array::<
+                    <#tail_ty as #crate_path::SliceLikePointee>::Element
+                >(len).ok()?;
+                let (layout, tail_offset) = layout.extend(tail).ok()?;
+                let layout = layout.pad_to_align();
+                ::core::option::Option::Some((layout, tail_offset))
+            }
+
+            #[inline]
+            unsafe fn write_header(ptr: *mut u8, header: Self::Header) {
+                let #header_pat = header;
+                let layout = ::core::alloc::Layout::new::<()>();
+                #(
+                    let (layout, offset) = match layout
+                        .extend(::core::alloc::Layout::new::<#header_tys>())
+                    {
+                        ::core::result::Result::Ok(result) => result,
+                        ::core::result::Result::Err(_) => {
+                            ::core::unreachable!()
+                        }
+                    };
+                    let field_ptr = unsafe { ptr.add(offset) };
+                    unsafe {
+                        field_ptr.cast::<#header_tys>().write(#header_names);
+                    }
+                )*
+                let _ = layout;
+            }
+        }
+    }
 }
 
 /// Generates a `Pointee` implementation for trait object of the labeled trait.
 ///
+/// `LayoutFromMetadata`, `UnsizeFrom`, `ValidateMetadata`, and `DynPointee`
+/// implementations are generated alongside it.
+///
+/// If the trait has associated types, the implementations are generic over
+/// them, e.g. `dyn Stream<Item = T>` for every `T` which satisfies the bounds
+/// on `Stream::Item`. Associated types with a `where Self: Sized` bound are not
+/// part of the trait object, and generic associated types are not supported.
+///
+/// The trait must be dyn-compatible. Methods which are generic, don't take
+/// `self`, or use `Self` in their signature must have a `where Self: Sized`
+/// bound, and the trait may not have associated consts.
+///
 /// # Arguments
 ///
 /// `#[pointee(...)]` takes the following arguments:
 ///
 /// - `crate = ...`: Chooses an alternative crate path to import ptr_meta from.
+/// - `auto_traits`: Also generates implementations for the trait object with `+
+///   Send`, `+ Sync`, and `+ Send + Sync`.
+/// - `auto_traits(...)`: Also generates implementations for the trait object
+///   with every combination of the listed auto traits. `Send`, `Sync`, `Unpin`,
+///   `UnwindSafe`, and `RefUnwindSafe` refer to the standard library traits,
+///   and other auto traits must be given by path. Each auto trait may only be
+///   listed once.
+/// - `upcast(...)`: Implements `Upcast` for the trait object to each of the
+///   listed supertraits, which upcasts references to it. With native trait
+///   upcasting (Rust 1.86 and later), also implements `UpcastMetadata`, which
+///   upcasts metadata and raw pointers. On earlier compilers, the trait gets a
+///   hidden supertrait which records the supertrait vtables, so it can only be
+///   implemented for sized types. Each listed supertrait must have the same
+///   name and generic arguments as one of the trait's supertraits, but may be
+///   qualified differently.
+/// - `skip_check`: Skips checking that the trait is dyn-compatible. Violations
+///   are then reported by rustc on the generated implementations instead of on
+///   their causes.
 #[proc_macro_attribute]
 pub fn pointee(
     attr: proc_macro::TokenStream,
@@ -106,20 +946,570 @@ fn pointee_impl(
     attributes: Attributes,
     item: ItemTrait,
 ) -> Result<TokenStream, Error> {
-    let ident = &item.ident;
+    attributes.check_pointee_args()?;
+
+    let auto_traits = match &attributes.auto_traits {
+        Some(auto_traits) => auto_trait_paths(auto_traits)?,
+        None => Vec::new(),
+    };
+
+    if attributes.skip_check.is_none() {
+        check_dyn_compatible(&item)?;
+    }
+    let object = TraitObject::new(&item)?;
+
+    let combinations = (0..1usize << auto_traits.len())
+        .map(|mask| {
+            let bounds = auto_traits
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+                .map(|(_, path)| path);
+            quote! { #(+ #bounds)* }
+        })
+        .collect::<Vec<_>>();
+
+    let mut result = match &attributes.upcast {
+        Some(supertraits) => pointee_upcasts(
+            &attributes,
+            &item,
+            &object,
+            supertraits,
+            &combinations,
+        )?,
+        None => quote! { #item },
+    };
+    for auto_traits in combinations {
+        result.extend(pointee_trait_object(&attributes, &object, auto_traits));
+    }
+    Ok(result)
+}
+
+// Resolves the standard library's auto traits by name so they don't need to be
+// imported, and checks that no auto trait is listed twice.
+//
+// Other auto traits can only be given by path because a single identifier is
+// more likely to be a trait which isn't an auto trait, which rustc would
+// otherwise report once for every combination.
+fn auto_trait_paths(
+    auto_traits: &Punctuated<Path, Token![,]>,
+) -> Result<Vec<Path>, Error> {
+    let mut paths = Vec::<Path>::new();
+    for auto_trait in auto_traits.iter() {
+        let path = match auto_trait.get_ident() {
+            Some(ident) => match ident.to_string().as_str() {
+                "Send" | "Sync" | "Unpin" => {
+                    parse_quote! { ::core::marker::#ident }
+                }
+                "UnwindSafe" | "RefUnwindSafe" => {
+                    parse_quote! { ::core::panic::#ident }
+                }
+                _ => {
+                    return Err(Error::new_spanned(
+                        auto_trait,
+                        format!(
+                            "`{ident}` is not an auto trait\nhelp: \
+                             `auto_traits(...)` accepts `Send`, `Sync`, \
+                             `Unpin`, `UnwindSafe`, `RefUnwindSafe`, and the \
+                             paths of other auto traits"
+                        ),
+                    ));
+                }
+            },
+            None => auto_trait.clone(),
+        };
+
+        let tokens = path.to_token_stream().to_string();
+        if paths
+            .iter()
+            .any(|listed| listed.to_token_stream().to_string() == tokens)
+        {
+            return Err(Error::new_spanned(
+                auto_trait,
+                format!(
+                    "`{}` is listed more than once in `auto_traits(...)`",
+                    auto_trait.to_token_stream(),
+                ),
+            ));
+        }
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+// Generates the impls for the trait object of `item` with additional
+// `auto_traits`.
+fn pointee_trait_object(
+    attributes: &Attributes,
+    object: &TraitObject,
+    auto_traits: TokenStream,
+) -> TokenStream {
+    let bound = &object.bound;
     let crate_path = attributes.crate_path();
 
-    let (impl_generics, ty_generics, where_clause) =
-        item.generics.split_for_impl();
+    let (impl_generics, _, where_clause) = object.generics.split_for_impl();
 
-    Ok(quote! {
-        #item
+    let mut unsize_generics = object.generics.clone();
+    unsize_generics
+        .params
+        .insert(0, parse_quote! { '__ptr_meta_dyn });
+    unsize_generics.params.push(parse_quote! {
+        __PtrMetaT: #bound #auto_traits + '__ptr_meta_dyn
+    });
+    let (unsize_impl_generics, _, unsize_where_clause) =
+        unsize_generics.split_for_impl();
 
+    // The impls are for `dyn Trait + '_` so that trait objects with any
+    // lifetime bound implement them, not just `dyn Trait + 'static`. Pointer
+    // metadata doesn't depend on the lifetime of the trait object.
+    quote! {
         unsafe impl #impl_generics #crate_path::Pointee for
-            (dyn #ident #ty_generics + '_)
+            (dyn #bound #auto_traits + '_)
         #where_clause
         {
             type Metadata = #crate_path::DynMetadata<Self>;
         }
-    })
+
+        unsafe impl #impl_generics #crate_path::LayoutFromMetadata for
+            (dyn #bound #auto_traits + '_)
+        #where_clause
+        {
+            #[inline]
+            fn layout_for_metadata(
+                metadata: #crate_path::DynMetadata<Self>,
+            ) -> ::core::option::Option<::core::alloc::Layout> {
+                ::core::option::Option::Some(metadata.layout())
+            }
+        }
+
+        impl #impl_generics #crate_path::ValidateMetadata for
+            (dyn #bound #auto_traits + '_)
+        #where_clause
+        {
+            type Error = ::core::convert::Infallible;
+
+            #[inline]
+            fn validate_metadata(
+                _: #crate_path::DynMetadata<Self>,
+            ) -> ::core::result::Result<(), Self::Error> {
+                ::core::result::Result::Ok(())
+            }
+        }
+
+        impl #impl_generics #crate_path::DynPointee for
+            (dyn #bound #auto_traits + '_)
+        #where_clause
+        {}
+
+        unsafe impl #unsize_impl_generics
+            #crate_path::UnsizeFrom<__PtrMetaT>
+            for (dyn #bound #auto_traits + '__ptr_meta_dyn)
+        #unsize_where_clause
+        {
+            #[inline]
+            fn unsize_metadata() -> #crate_path::DynMetadata<Self> {
+                #crate_path::__private::vtable_for::<__PtrMetaT, Self>(
+                    |ptr| ptr,
+                )
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::TokenStream;
+    use quote::quote;
+
+    use super::{attributes::Attributes, derive_pointee_impl, pointee_impl};
+
+    // Checks the message and help of the diagnostic for a derive input which
+    // fails to derive `Pointee`.
+    #[track_caller]
+    fn assert_diagnostic(input: TokenStream, expected: &str, help: &str) {
+        let input = syn::parse2(input).unwrap();
+        let message = derive_pointee_impl(input).unwrap_err().to_string();
+        let (first, rest) = message.split_once("\nhelp: ").unwrap();
+        assert_eq!(first, expected);
+        assert_eq!(rest, help);
+    }
+
+    #[test]
+    fn sized_tails() {
+        let help = "make the last field a slice like `[T]`, `str`, or a trait \
+                    object like `dyn Trait`, or remove `#[derive(Pointee)]` \
+                    to use the impl for sized types";
+        let cases = [
+            (quote! { struct S { a: u8, b: u32 } }, "u32"),
+            (quote! { struct S { a: u8, b: [u8; 4] } }, "[u8 ; 4]"),
+            (quote! { struct S(u8, (u8, bool)); }, "(u8 , bool)"),
+            (quote! { struct S<'a> { a: &'a [u8] } }, "& 'a [u8]"),
+            (quote! { struct S { a: *const str } }, "* const str"),
+        ];
+        for (input, ty) in cases {
+            assert_diagnostic(
+                input,
+                &format!(
+                    "the last field of a struct deriving `Pointee` must be \
+                     dynamically-sized, but `{ty}` is always sized"
+                ),
+                help,
+            );
+        }
+    }
+
+    #[test]
+    fn generic_tails() {
+        assert_diagnostic(
+            quote! { struct Node<T> { header: u32, tail: T } },
+            "the last field of a struct deriving `Pointee` must be \
+             dynamically-sized, but `T` is always sized because it is not \
+             `?Sized`",
+            "add a `?Sized` bound to `T` and list the unsized types it may be \
+             with `#[ptr_meta(tail_variants(...))]`",
+        );
+        for input in [
+            quote! { struct Node<T: ?Sized> { header: u32, tail: T } },
+            quote! { struct Node<T> where T: ?Sized { header: u32, tail: T } },
+        ] {
+            assert_diagnostic(
+                input,
+                "the last field of a struct deriving `Pointee` must be \
+                 dynamically-sized, but `T` may be sized",
+                "list the unsized types `T` may be with \
+                 `#[ptr_meta(tail_variants(...))]`",
+            );
+        }
+        assert_diagnostic(
+            quote! {
+                #[ptr_meta(tail_variants(str, u64))]
+                struct Node<T: ?Sized> { header: u32, tail: T }
+            },
+            "the last field of a struct deriving `Pointee` must be \
+             dynamically-sized, but `u64` is always sized",
+            "make the last field a slice like `[T]`, `str`, or a trait object \
+             like `dyn Trait`, or remove `#[derive(Pointee)]` to use the impl \
+             for sized types",
+        );
+    }
+
+    #[test]
+    fn missing_repr() {
+        assert_diagnostic(
+            quote! {
+                #[ptr_meta(plain)]
+                struct S { a: u8, b: [u8] }
+            },
+            "`plain` requires the struct to be `#[repr(C)]` or \
+             `#[repr(transparent)]`",
+            "add `#[repr(C)]` to the struct",
+        );
+        assert_diagnostic(
+            quote! {
+                #[ptr_meta(transparent_casts)]
+                #[repr(C)]
+                struct S { a: str }
+            },
+            "`transparent_casts` requires the struct to be \
+             `#[repr(transparent)]`",
+            "add `#[repr(transparent)]` to the struct",
+        );
+    }
+
+    #[test]
+    fn not_structs() {
+        assert_diagnostic(
+            quote! { enum E { A(u8), B } },
+            "enums are always sized, so they already implement `Pointee` \
+             through the impl for sized types",
+            "remove `#[derive(Pointee)]`, or use a trait object with \
+             `#[ptr_meta::pointee]` on its trait to store values of different \
+             types behind one pointer type",
+        );
+        assert_diagnostic(
+            quote! { union U { a: u8, b: u16 } },
+            "unions are always sized, so they already implement `Pointee` \
+             through the impl for sized types",
+            "remove `#[derive(Pointee)]`",
+        );
+        assert_diagnostic(
+            quote! { struct S; },
+            "fieldless structs are always sized, so they already implement \
+             `Pointee` through the impl for sized types",
+            "remove `#[derive(Pointee)]`, or add a dynamically-sized last \
+             field",
+        );
+    }
+
+    #[test]
+    fn unsized_tails() {
+        for input in [
+            quote! { struct S { a: u8, b: [u8] } },
+            quote! { struct S<T> { a: u8, b: [T] } },
+            quote! { struct S(u8, str); },
+            quote! { struct S { a: dyn core::any::Any } },
+            quote! {
+                #[ptr_meta(tail_variants([u8], dyn core::any::Any))]
+                struct S<T: ?Sized> { a: T }
+            },
+        ] {
+            let input = syn::parse2(input).unwrap();
+            assert!(derive_pointee_impl(input).is_ok());
+        }
+    }
+
+    #[test]
+    fn generic_associated_types() {
+        let item = syn::parse2(quote! {
+            trait Lend {
+                type Item<'a>;
+            }
+        })
+        .unwrap();
+        let message = pointee_impl(Attributes::default(), item)
+            .unwrap_err()
+            .to_string();
+        assert!(message.starts_with(
+            "`#[ptr_meta::pointee]` does not support generic associated types"
+        ));
+
+        let item = syn::parse2(quote! {
+            trait Lend {
+                type Item<'a> where Self: Sized;
+            }
+        })
+        .unwrap();
+        assert!(pointee_impl(Attributes::default(), item).is_ok());
+    }
+
+    // Checks the message and help of each diagnostic for a trait which is not
+    // dyn-compatible.
+    #[track_caller]
+    fn assert_dyn_incompatible(input: TokenStream, expected: &[(&str, &str)]) {
+        let item = syn::parse2(input).unwrap();
+        let errors = pointee_impl(Attributes::default(), item).unwrap_err();
+        let messages = errors
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>();
+        let messages = messages
+            .iter()
+            .map(|message| message.split_once("\nhelp: ").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, expected);
+    }
+
+    #[test]
+    fn dyn_incompatible_methods() {
+        let help = |method: &str| {
+            format!(
+                "add `where Self: Sized` to `{method}` to exclude it from the \
+                 trait object"
+            )
+        };
+        let new_help = help("new");
+        let map_help = help("map");
+        let merge_help = help("merge");
+        let run_help = help("run");
+        let each_help = help("each");
+        let iter_help = help("iter");
+
+        assert_dyn_incompatible(
+            quote! {
+                trait Shape {
+                    fn new() -> Self;
+                    fn map<T>(&self, value: T);
+                    fn merge(&self, other: &Self);
+                    async fn run(&self);
+                    fn each(&self, f: impl Fn());
+                    fn iter(&self) -> impl Iterator<Item = u8>;
+                }
+            },
+            &[
+                (
+                    "the trait `Shape` is not dyn-compatible because `new` \
+                     has no `self` parameter",
+                    &new_help,
+                ),
+                (
+                    "the trait `Shape` is not dyn-compatible because method \
+                     `new` references the `Self` type in its return type",
+                    &new_help,
+                ),
+                (
+                    "the trait `Shape` is not dyn-compatible because method \
+                     `map` has generic parameters",
+                    &map_help,
+                ),
+                (
+                    "the trait `Shape` is not dyn-compatible because method \
+                     `merge` references the `Self` type in its parameters",
+                    &merge_help,
+                ),
+                (
+                    "the trait `Shape` is not dyn-compatible because method \
+                     `run` is `async`",
+                    &run_help,
+                ),
+                (
+                    "the trait `Shape` is not dyn-compatible because method \
+                     `each` has an `impl Trait` parameter",
+                    &each_help,
+                ),
+                (
+                    "the trait `Shape` is not dyn-compatible because method \
+                     `iter` returns `impl Trait`",
+                    &iter_help,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn dyn_incompatible_traits() {
+        assert_dyn_incompatible(
+            quote! {
+                trait Shape: Sized {
+                    const SIDES: usize;
+                }
+            },
+            &[
+                (
+                    "the trait `Shape` is not dyn-compatible because it \
+                     requires `Self: Sized`",
+                    "remove the `Sized` supertrait and add `where Self: \
+                     Sized` to the methods which need it",
+                ),
+                (
+                    "the trait `Shape` is not dyn-compatible because it \
+                     contains the associated const `SIDES`",
+                    "replace the const with a method which returns its value",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn dyn_compatible() {
+        let item = syn::parse2(quote! {
+            trait Shape {
+                type Unit;
+
+                fn area(&self) -> Self::Unit;
+                fn scale<'a>(&'a mut self, by: &'a <Self as Shape>::Unit);
+                fn new() -> Self where Self: Sized;
+                fn map<T>(self, value: T) -> Self where Self: Sized;
+            }
+        })
+        .unwrap();
+        assert!(pointee_impl(Attributes::default(), item).is_ok());
+
+        let mut attributes = Attributes::default();
+        attributes.skip_check = Some(syn::parse_quote! { skip_check });
+        let item = syn::parse2(quote! {
+            trait Shape {
+                fn new() -> Self;
+            }
+        })
+        .unwrap();
+        assert!(pointee_impl(attributes, item).is_ok());
+    }
+
+    #[test]
+    fn invalid_auto_traits() {
+        let item: syn::ItemTrait = syn::parse2(quote! {
+            trait Shape {}
+        })
+        .unwrap();
+        let cases = [
+            (
+                syn::parse_quote! { Send, Sync, Send },
+                "`Send` is listed more than once in `auto_traits(...)`",
+            ),
+            (
+                syn::parse_quote! { Send, Debug },
+                "`Debug` is not an auto trait\nhelp: `auto_traits(...)` \
+                 accepts `Send`, `Sync`, `Unpin`, `UnwindSafe`, \
+                 `RefUnwindSafe`, and the paths of other auto traits",
+            ),
+        ];
+        for (auto_traits, expected) in cases {
+            let mut attributes = Attributes::default();
+            attributes.auto_traits = Some(auto_traits);
+            let message = pointee_impl(attributes, item.clone())
+                .unwrap_err()
+                .to_string();
+            assert_eq!(message, expected);
+        }
+    }
+
+    #[test]
+    fn derive_only_args() {
+        let mut attributes = Attributes::default();
+        attributes.plain = Some(syn::parse_quote! { plain });
+        attributes.transparent_casts =
+            Some(syn::parse_quote! { transparent_casts });
+        attributes.len_field = Some(syn::parse_quote! { len });
+        let item = syn::parse2(quote! {
+            trait Shape {}
+        })
+        .unwrap();
+        let messages = pointee_impl(attributes, item)
+            .unwrap_err()
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "`plain` is not supported by `#[ptr_meta::pointee]`",
+                "`transparent_casts` is not supported by \
+                 `#[ptr_meta::pointee]`",
+                "`len_field` is not supported by `#[ptr_meta::pointee]`",
+            ],
+        );
+    }
+
+    #[test]
+    fn upcast_to_non_supertrait() {
+        let mut attributes = Attributes::default();
+        attributes.upcast = Some(syn::parse_quote! { Debug, Display });
+        let item = syn::parse2(quote! {
+            trait Shape: Debug {}
+        })
+        .unwrap();
+        let message = pointee_impl(attributes, item).unwrap_err().to_string();
+        assert_eq!(
+            message,
+            "`Display` is not a supertrait of `Shape`\nhelp: add `Display` to \
+             the supertraits of `Shape`, or remove it from `upcast(...)`",
+        );
+    }
+
+    #[test]
+    fn upcast_to_qualified_supertrait() {
+        let mut attributes = Attributes::default();
+        attributes.upcast = Some(syn::parse_quote! {
+            core::fmt::Debug, Source<u32>
+        });
+        let item = syn::parse2(quote! {
+            trait Shape: Debug + crate::Source<u32> {}
+        })
+        .unwrap();
+        assert!(pointee_impl(attributes, item).is_ok());
+
+        let mut attributes = Attributes::default();
+        attributes.upcast = Some(syn::parse_quote! { Source<u64> });
+        let item = syn::parse2(quote! {
+            trait Shape: Source<u32> {}
+        })
+        .unwrap();
+        let message = pointee_impl(attributes, item).unwrap_err().to_string();
+        assert_eq!(
+            message,
+            "`Source < u64 >` is not a supertrait of `Shape`\nhelp: add \
+             `Source < u64 >` to the supertraits of `Shape`, or remove it \
+             from `upcast(...)`",
+        );
+    }
 }
@@ -0,0 +1,148 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    parse_quote,
+    visit_mut::{self, VisitMut},
+    Error, GenericParam, Generics, Ident, ItemTrait, TraitBoundModifier,
+    TraitItem, Type, TypeParamBound, WherePredicate,
+};
+
+/// The trait object type of a trait to generate impls for.
+///
+/// `dyn Trait` must name every associated type of `Trait`, so the impls are
+/// generic over one additional type parameter per associated type. Those
+/// parameters have the bounds of the associated type declarations.
+pub struct TraitObject {
+    pub generics: Generics,
+    pub bound: TokenStream,
+}
+
+impl TraitObject {
+    pub fn new(item: &ItemTrait) -> Result<Self, Error> {
+        let ident = &item.ident;
+        let assoc_types = item
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                TraitItem::Type(ty) if !requires_sized(&ty.generics) => {
+                    Some(ty)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(ty) =
+            assoc_types.iter().find(|ty| !ty.generics.params.is_empty())
+        {
+            return Err(Error::new_spanned(
+                &ty.generics,
+                "`#[ptr_meta::pointee]` does not support generic associated \
+                 types because trait objects can't name them\nhelp: add a \
+                 `where Self: Sized` bound to the associated type to exclude \
+                 it from the trait object",
+            ));
+        }
+
+        let params = assoc_types
+            .iter()
+            .map(|ty| (&ty.ident, format_ident!("__PtrMeta{}", ty.ident)))
+            .collect::<Vec<_>>();
+        let mut substitute = SubstituteAssoc { params: &params };
+
+        let mut generics = item.generics.clone();
+        for (ty, (_, param)) in assoc_types.iter().zip(&params) {
+            let mut bounds = ty.bounds.clone();
+            for bound in bounds.iter_mut() {
+                substitute.visit_type_param_bound_mut(bound);
+            }
+            generics.params.push(parse_quote! { #param: #bounds });
+        }
+
+        let args = item.generics.params.iter().map(|generic| match generic {
+            GenericParam::Lifetime(lifetime) => {
+                let lifetime = &lifetime.lifetime;
+                quote! { #lifetime }
+            }
+            GenericParam::Type(ty) => {
+                let ident = &ty.ident;
+                quote! { #ident }
+            }
+            GenericParam::Const(param) => {
+                let ident = &param.ident;
+                quote! { #ident }
+            }
+        });
+        let bindings = params
+            .iter()
+            .map(|(assoc, param)| quote! { #assoc = #param });
+        let args = args.chain(bindings).collect::<Vec<_>>();
+        let bound = if args.is_empty() {
+            quote! { #ident }
+        } else {
+            quote! { #ident<#(#args),*> }
+        };
+
+        Ok(Self { generics, bound })
+    }
+}
+
+/// Returns whether an associated item has a `where Self: Sized` bound, which
+/// excludes it from the trait object.
+pub fn requires_sized(generics: &Generics) -> bool {
+    generics.where_clause.iter().any(|where_clause| {
+        where_clause.predicates.iter().any(|predicate| match predicate {
+            WherePredicate::Type(predicate) => {
+                matches!(
+                    &predicate.bounded_ty,
+                    Type::Path(path)
+                        if path.qself.is_none() && path.path.is_ident("Self")
+                ) && predicate.bounds.iter().any(is_sized_bound)
+            }
+            _ => false,
+        })
+    })
+}
+
+pub fn is_sized_bound(bound: &TypeParamBound) -> bool {
+    match bound {
+        TypeParamBound::Trait(bound) => {
+            matches!(bound.modifier, TraitBoundModifier::None)
+                && bound
+                    .path
+                    .segments
+                    .last()
+                    .is_some_and(|segment| segment.ident == "Sized")
+        }
+        _ => false,
+    }
+}
+
+// Replaces every use of `Self::Assoc` with the type parameter for `Assoc`.
+struct SubstituteAssoc<'a> {
+    params: &'a [(&'a Ident, Ident)],
+}
+
+impl VisitMut for SubstituteAssoc<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::Path(path) = ty {
+            let segments = &path.path.segments;
+            if path.qself.is_none()
+                && path.path.leading_colon.is_none()
+                && segments.len() == 2
+                && segments[0].ident == "Self"
+                && segments[1].arguments.is_empty()
+            {
+                if let Some((_, param)) = self
+                    .params
+                    .iter()
+                    .find(|(assoc, _)| segments[1].ident == **assoc)
+                {
+                    *ty = parse_quote! { #param };
+                    return;
+                }
+            }
+        }
+
+        visit_mut::visit_type_mut(self, ty);
+    }
+}
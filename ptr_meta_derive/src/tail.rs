@@ -0,0 +1,116 @@
+use quote::ToTokens;
+use syn::{
+    Error, Generics, Ident, TraitBoundModifier, Type, TypeParamBound,
+    WherePredicate,
+};
+
+const PRIMITIVES: &[&str] = &[
+    "bool", "char", "f32", "f64", "i8", "i16", "i32", "i64", "i128", "isize",
+    "u8", "u16", "u32", "u64", "u128", "usize",
+];
+
+/// Returns an error if the last field of a struct deriving `Pointee` can be
+/// sized.
+///
+/// Sized structs already implement `Pointee` through the blanket impl, so a
+/// derived impl for one would conflict with it far from the cause.
+pub fn check_tail(ty: &Type, generics: &Generics) -> Result<(), Error> {
+    if is_always_sized(ty) {
+        return Err(Error::new_spanned(
+            ty,
+            format!(
+                "the last field of a struct deriving `Pointee` must be \
+                 dynamically-sized, but `{}` is always sized\nhelp: make the \
+                 last field a slice like `[T]`, `str`, or a trait object like \
+                 `dyn Trait`, or remove `#[derive(Pointee)]` to use the impl \
+                 for sized types",
+                ty.to_token_stream(),
+            ),
+        ));
+    }
+
+    if let Some(param) = generic_param(ty, generics) {
+        let message = if is_maybe_sized(param, generics) {
+            format!(
+                "the last field of a struct deriving `Pointee` must be \
+                 dynamically-sized, but `{param}` may be sized\nhelp: list \
+                 the unsized types `{param}` may be with \
+                 `#[ptr_meta(tail_variants(...))]`"
+            )
+        } else {
+            format!(
+                "the last field of a struct deriving `Pointee` must be \
+                 dynamically-sized, but `{param}` is always sized because it \
+                 is not `?Sized`\nhelp: add a `?Sized` bound to `{param}` and \
+                 list the unsized types it may be with \
+                 `#[ptr_meta(tail_variants(...))]`"
+            )
+        };
+        return Err(Error::new_spanned(ty, message));
+    }
+
+    Ok(())
+}
+
+fn is_always_sized(ty: &Type) -> bool {
+    match ty {
+        Type::Array(_)
+        | Type::BareFn(_)
+        | Type::Never(_)
+        | Type::Ptr(_)
+        | Type::Reference(_) => true,
+        Type::Tuple(tuple) => match tuple.elems.last() {
+            Some(last) => is_always_sized(last),
+            None => true,
+        },
+        Type::Path(path) => {
+            path.qself.is_none()
+                && PRIMITIVES.iter().any(|name| path.path.is_ident(name))
+        }
+        Type::Group(group) => is_always_sized(&group.elem),
+        Type::Paren(paren) => is_always_sized(&paren.elem),
+        _ => false,
+    }
+}
+
+fn generic_param<'a>(ty: &Type, generics: &'a Generics) -> Option<&'a Ident> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => generics
+            .type_params()
+            .map(|param| &param.ident)
+            .find(|&ident| path.path.is_ident(ident)),
+        Type::Group(group) => generic_param(&group.elem, generics),
+        Type::Paren(paren) => generic_param(&paren.elem, generics),
+        _ => None,
+    }
+}
+
+pub fn is_maybe_bound(bound: &TypeParamBound) -> bool {
+    matches!(
+        bound,
+        TypeParamBound::Trait(bound)
+            if matches!(bound.modifier, TraitBoundModifier::Maybe(_))
+    )
+}
+
+fn is_maybe_sized(param: &Ident, generics: &Generics) -> bool {
+    let in_params = generics
+        .type_params()
+        .filter(|ty| ty.ident == *param)
+        .any(|ty| ty.bounds.iter().any(is_maybe_bound));
+    let in_where_clause = generics.where_clause.iter().any(|where_clause| {
+        where_clause
+            .predicates
+            .iter()
+            .any(|predicate| match predicate {
+                WherePredicate::Type(predicate) => {
+                    generic_param(&predicate.bounded_ty, generics)
+                        == Some(param)
+                        && predicate.bounds.iter().any(is_maybe_bound)
+                }
+                _ => false,
+            })
+    });
+
+    in_params || in_where_clause
+}
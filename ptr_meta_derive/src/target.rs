@@ -0,0 +1,182 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse_quote,
+    punctuated::Punctuated,
+    visit_mut::{self, VisitMut},
+    DeriveInput, Error, Fields, GenericParam, Generics, Ident, Type,
+    TypeParamBound, WherePredicate,
+};
+
+use crate::tail::is_maybe_bound;
+
+/// A struct type to generate impls for.
+///
+/// Usually this is the struct with all of its generic parameters. With
+/// `tail_variants`, there is one target per tail type, with the generic
+/// parameter of the last field replaced by that type.
+pub struct Target {
+    pub ident: Ident,
+    pub generics: Generics,
+    pub self_ty: Type,
+    pub fields: Fields,
+    /// The type from `tail_variants` which was substituted for the last field.
+    pub variant: Option<Type>,
+}
+
+impl Target {
+    pub fn new(input: &DeriveInput, fields: &Fields) -> Self {
+        let ident = &input.ident;
+        let (_, ty_generics, _) = input.generics.split_for_impl();
+
+        Self {
+            ident: ident.clone(),
+            generics: input.generics.clone(),
+            self_ty: parse_quote! { #ident #ty_generics },
+            fields: fields.clone(),
+            variant: None,
+        }
+    }
+
+    pub fn tail_variants<'a>(
+        input: &DeriveInput,
+        fields: &Fields,
+        variants: impl IntoIterator<Item = &'a Type>,
+    ) -> Result<Vec<Self>, Error> {
+        let tail_ty = &fields.iter().next_back().unwrap().ty;
+        let Some(param) = input
+            .generics
+            .type_params()
+            .find(|param| is_param(tail_ty, &param.ident))
+        else {
+            return Err(Error::new_spanned(
+                tail_ty,
+                "`tail_variants` requires the type of the last field to be a \
+                 generic type parameter",
+            ));
+        };
+
+        Ok(variants
+            .into_iter()
+            .map(|variant| {
+                Self::substitute(input, fields, &param.ident, variant)
+            })
+            .collect())
+    }
+
+    fn substitute(
+        input: &DeriveInput,
+        fields: &Fields,
+        param: &Ident,
+        variant: &Type,
+    ) -> Self {
+        let ident = &input.ident;
+        let original = variant.clone();
+        let variant = match variant {
+            Type::TraitObject(_) => parse_quote! { (#variant) },
+            _ => variant.clone(),
+        };
+
+        let mut generics = input.generics.clone();
+        let mut param_bounds = Punctuated::<TypeParamBound, _>::new();
+        generics.params = generics
+            .params
+            .into_iter()
+            .filter_map(|generic| match generic {
+                GenericParam::Type(ty) if ty.ident == *param => {
+                    param_bounds = ty.bounds;
+                    None
+                }
+                generic => Some(generic),
+            })
+            .collect();
+        let param_bounds = param_bounds
+            .into_iter()
+            .filter(|bound| !is_maybe_bound(bound))
+            .collect::<Vec<_>>();
+        if !param_bounds.is_empty() {
+            generics
+                .make_where_clause()
+                .predicates
+                .push(parse_quote! { #param: #(#param_bounds)+* });
+        }
+
+        let mut substitute = Substitute {
+            param,
+            variant: &variant,
+        };
+        substitute.visit_generics_mut(&mut generics);
+        let mut fields = fields.clone();
+        substitute.visit_fields_mut(&mut fields);
+
+        let args = input.generics.params.iter().map(|generic| match generic {
+            GenericParam::Lifetime(lifetime) => {
+                let lifetime = &lifetime.lifetime;
+                quote! { #lifetime }
+            }
+            GenericParam::Type(ty) if ty.ident == *param => quote! { #variant },
+            GenericParam::Type(ty) => {
+                let ident = &ty.ident;
+                quote! { #ident }
+            }
+            GenericParam::Const(param) => {
+                let ident = &param.ident;
+                quote! { #ident }
+            }
+        });
+
+        Self {
+            ident: ident.clone(),
+            generics,
+            self_ty: parse_quote! { #ident<#(#args),*> },
+            fields,
+            variant: Some(original),
+        }
+    }
+
+    pub fn tail_ty(&self) -> &Type {
+        &self.fields.iter().next_back().unwrap().ty
+    }
+
+    /// Returns a where-predicate bounding `ty` by `bound`.
+    ///
+    /// Types substituted from `tail_variants` don't mention any generic
+    /// parameters, so a bound on one which doesn't hold would be trivially
+    /// false and rejected by the compiler. Quantifying over a lifetime defers
+    /// the check to where the impl is used, so the impl just doesn't apply.
+    pub fn bound(&self, ty: &Type, bound: TokenStream) -> WherePredicate {
+        if self.variant.is_some() {
+            parse_quote! { for<'__ptr_meta> #ty: #bound }
+        } else {
+            parse_quote! { #ty: #bound }
+        }
+    }
+}
+
+fn is_param(ty: &Type, param: &Ident) -> bool {
+    match ty {
+        Type::Path(path) => path.qself.is_none() && path.path.is_ident(param),
+        Type::Group(group) => is_param(&group.elem, param),
+        Type::Paren(paren) => is_param(&paren.elem, param),
+        _ => false,
+    }
+}
+
+// Replaces every use of a generic type parameter with another type.
+struct Substitute<'a> {
+    param: &'a Ident,
+    variant: &'a Type,
+}
+
+impl VisitMut for Substitute<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::Path(path) = ty {
+            if path.qself.is_none() && path.path.is_ident(self.param) {
+                *ty = self.variant.clone();
+                return;
+            }
+        }
+
+        visit_mut::visit_type_mut(self, ty);
+    }
+}
@@ -0,0 +1,273 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse_quote, punctuated::Punctuated, Error, ItemTrait, Path, Token,
+    TypeParamBound,
+};
+
+use crate::{attributes::Attributes, object::TraitObject};
+
+/// Generates `Upcast` and `UpcastMetadata` implementations for the trait object
+/// of `item` to each of `supertraits`, and emits `item` alongside them.
+///
+/// With native trait upcasting, the implementations coerce pointers directly.
+/// Otherwise, `item` gets a hidden supertrait which records the vtable of each
+/// supertrait for the implementing type, and only `Upcast` is implemented by
+/// reading those vtables through a value.
+pub fn pointee_upcasts(
+    attributes: &Attributes,
+    item: &ItemTrait,
+    object: &TraitObject,
+    supertraits: &Punctuated<Path, Token![,]>,
+    combinations: &[TokenStream],
+) -> Result<TokenStream, Error> {
+    for supertrait in supertraits.iter() {
+        check_supertrait(item, supertrait)?;
+    }
+
+    let crate_path = attributes.crate_path();
+    let bound = &object.bound;
+
+    let mut generics = object.generics.clone();
+    generics.params.insert(0, parse_quote! { '__ptr_meta_dyn });
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    let mut impls = TokenStream::new();
+    for (i, supertrait) in supertraits.iter().enumerate() {
+        for auto_traits in combinations {
+            let super_ty = quote! {
+                dyn #supertrait #auto_traits + '__ptr_meta_dyn
+            };
+            let self_ty = quote! {
+                (dyn #bound #auto_traits + '__ptr_meta_dyn)
+            };
+
+            impls.extend(if cfg!(ptr_meta_trait_upcasting) {
+                native_impls(
+                    &crate_path,
+                    &impl_generics,
+                    where_clause,
+                    &super_ty,
+                    &self_ty,
+                )
+            } else {
+                fallback_impls(
+                    &crate_path,
+                    &impl_generics,
+                    where_clause,
+                    &super_ty,
+                    &self_ty,
+                    supertrait,
+                    i,
+                )
+            });
+        }
+    }
+
+    let item = if cfg!(ptr_meta_trait_upcasting) {
+        item.to_token_stream()
+    } else {
+        recorder(item, supertraits)
+    };
+    Ok(quote! {
+        #item
+        #impls
+    })
+}
+
+fn native_impls(
+    crate_path: &Path,
+    impl_generics: &impl ToTokens,
+    where_clause: Option<&syn::WhereClause>,
+    super_ty: &TokenStream,
+    self_ty: &TokenStream,
+) -> TokenStream {
+    quote! {
+        // SAFETY: Upcasting coercions return references to the same value.
+        unsafe impl #impl_generics #crate_path::Upcast<#super_ty>
+            for #self_ty
+        #where_clause
+        {
+            #[inline]
+            fn upcast_ref(&self) -> &(#super_ty) {
+                self
+            }
+
+            #[inline]
+            fn upcast_mut(&mut self) -> &mut (#super_ty) {
+                self
+            }
+        }
+
+        // SAFETY: Upcasting coercions keep the data address of the pointer
+        // and replace its metadata with the metadata of the supertrait for
+        // the same concrete type.
+        unsafe impl #impl_generics
+            #crate_path::UpcastMetadata<#super_ty>
+            for #self_ty
+        #where_clause
+        {
+            #[inline]
+            fn upcast_metadata(
+                metadata: #crate_path::DynMetadata<Self>,
+            ) -> #crate_path::DynMetadata<#super_ty> {
+                let ptr: *const (#super_ty) =
+                    #crate_path::from_raw_parts::<Self>(
+                        ::core::ptr::null::<()>(),
+                        metadata,
+                    );
+                // SAFETY: `#super_ty` is a trait object type.
+                unsafe { #crate_path::__private::dyn_metadata(ptr) }
+            }
+
+            #[inline]
+            fn upcast_ptr(ptr: *const Self) -> *const (#super_ty) {
+                ptr
+            }
+
+            #[inline]
+            fn upcast_ptr_mut(ptr: *mut Self) -> *mut (#super_ty) {
+                ptr
+            }
+        }
+    }
+}
+
+fn fallback_impls(
+    crate_path: &Path,
+    impl_generics: &impl ToTokens,
+    where_clause: Option<&syn::WhereClause>,
+    super_ty: &TokenStream,
+    self_ty: &TokenStream,
+    supertrait: &Path,
+    index: usize,
+) -> TokenStream {
+    let upcast_ref = format_ident!("__ptr_meta_upcast_ref_{index}");
+    let upcast_mut = format_ident!("__ptr_meta_upcast_mut_{index}");
+
+    quote! {
+        // SAFETY: The recorded upcasting methods return references to the
+        // same value.
+        unsafe impl #impl_generics #crate_path::Upcast<#super_ty>
+            for #self_ty
+        #where_clause
+        {
+            #[inline]
+            fn upcast_ref(&self) -> &(#super_ty) {
+                let ptr: *const (dyn #supertrait + '_) = self.#upcast_ref();
+                // SAFETY: The value implements the auto traits of `Self` and
+                // outlives its lifetime bound, so it can be viewed as a
+                // `#super_ty`.
+                unsafe {
+                    &*#crate_path::__private::cast_dyn::<_, #super_ty>(ptr)
+                }
+            }
+
+            #[inline]
+            fn upcast_mut(&mut self) -> &mut (#super_ty) {
+                let ptr: *mut (dyn #supertrait + '_) = self.#upcast_mut();
+                // SAFETY: The value implements the auto traits of `Self` and
+                // outlives its lifetime bound, so it can be viewed as a
+                // `#super_ty`.
+                unsafe {
+                    &mut *#crate_path::__private::cast_dyn::<_, #super_ty>(ptr)
+                        .cast_mut()
+                }
+            }
+        }
+    }
+}
+
+fn check_supertrait(item: &ItemTrait, supertrait: &Path) -> Result<(), Error> {
+    let is_supertrait = item.supertraits.iter().any(|bound| match bound {
+        TypeParamBound::Trait(bound) => same_trait(&bound.path, supertrait),
+        _ => false,
+    });
+
+    if is_supertrait {
+        Ok(())
+    } else {
+        let tokens = supertrait.to_token_stream().to_string();
+        let ident = &item.ident;
+        Err(Error::new_spanned(
+            supertrait,
+            format!(
+                "`{tokens}` is not a supertrait of `{ident}`\nhelp: add \
+                 `{tokens}` to the supertraits of `{ident}`, or remove it \
+                 from `upcast(...)`"
+            ),
+        ))
+    }
+}
+
+// Returns whether two paths name the same trait with the same generic
+// arguments. Paths may be qualified differently, so only their last segments
+// are compared.
+fn same_trait(a: &Path, b: &Path) -> bool {
+    match (a.segments.last(), b.segments.last()) {
+        (Some(a), Some(b)) => {
+            a.ident == b.ident
+                && a.arguments.to_token_stream().to_string()
+                    == b.arguments.to_token_stream().to_string()
+        }
+        _ => false,
+    }
+}
+
+// Generates `item` with a hidden supertrait which records the vtables of each
+// of `supertraits`, and the blanket implementation of that supertrait.
+fn recorder(
+    item: &ItemTrait,
+    supertraits: &Punctuated<Path, Token![,]>,
+) -> TokenStream {
+    let vis = &item.vis;
+    let recorder = format_ident!("__PtrMetaUpcast{}", item.ident);
+    let generics = &item.generics;
+    let (_, ty_generics, where_clause) = generics.split_for_impl();
+
+    let mut item = item.clone();
+    item.supertraits
+        .push(parse_quote! { #recorder #ty_generics });
+
+    let upcast_ref = (0..supertraits.len())
+        .map(|i| format_ident!("__ptr_meta_upcast_ref_{i}"))
+        .collect::<Vec<_>>();
+    let upcast_mut = (0..supertraits.len())
+        .map(|i| format_ident!("__ptr_meta_upcast_mut_{i}"))
+        .collect::<Vec<_>>();
+    let supertraits = supertraits.iter().collect::<Vec<_>>();
+
+    let mut impl_generics = generics.clone();
+    impl_generics.params.push(parse_quote! {
+        __PtrMetaT: #(#supertraits)+*
+    });
+    let (impl_generics, ..) = impl_generics.split_for_impl();
+
+    quote! {
+        #item
+
+        #[doc(hidden)]
+        #vis trait #recorder #generics #where_clause {
+            #(
+                fn #upcast_ref(&self) -> &dyn #supertraits;
+                fn #upcast_mut(&mut self) -> &mut dyn #supertraits;
+            )*
+        }
+
+        impl #impl_generics #recorder #ty_generics for __PtrMetaT
+        #where_clause
+        {
+            #(
+                #[inline]
+                fn #upcast_ref(&self) -> &dyn #supertraits {
+                    self
+                }
+
+                #[inline]
+                fn #upcast_mut(&mut self) -> &mut dyn #supertraits {
+                    self
+                }
+            )*
+        }
+    }
+}